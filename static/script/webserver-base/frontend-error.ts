@@ -3,6 +3,22 @@
  * @module
  */
 
+/**
+ * How severe a frontend error is.
+ */
+export type FrontendErrorSeverity = "debug" | "info" | "warning" | "error" | "fatal";
+
+/**
+ * Something that happened before an error (e.g. a click, navigation or network request).
+ */
+export type FrontendBreadcrumb = {
+  timestamp: string;
+  category: string | null;
+  message: string | null;
+  severity: FrontendErrorSeverity | null;
+  data: Record<string, unknown>;
+};
+
 /**
  * The payload of a frontend error report.
  */
@@ -15,8 +31,59 @@ type FrontendErrorPayload = {
   stackTrace: string | null;
   currentUrl: string | null;
   timestamp: string | null;
+
+  severity: FrontendErrorSeverity | null;
+  name: string | null;
+  userAgent: string | null;
+  viewport: { width: number; height: number } | null;
+  breadcrumbs: FrontendBreadcrumb[];
+  context: Record<string, unknown>;
 };
 
+/** The most recent breadcrumbs are sent along with every error report. */
+const MAX_BREADCRUMBS: number = 20;
+const breadcrumbs: FrontendBreadcrumb[] = [];
+const errorContext: Record<string, unknown> = {};
+
+/**
+ * Records a breadcrumb that is sent along with subsequent error reports.
+ */
+export function addBreadcrumb(
+  message: string,
+  category: string | null = null,
+  data: Record<string, unknown> = {},
+  severity: FrontendErrorSeverity | null = null,
+): void {
+  breadcrumbs.push({ timestamp: new Date().toISOString(), category, message, severity, data });
+  if (breadcrumbs.length > MAX_BREADCRUMBS) {
+    breadcrumbs.shift();
+  }
+}
+
+/**
+ * Sets a value (e.g. a feature flag or the current route) that is sent along with subsequent error reports.
+ */
+export function setErrorContext(key: string, value: unknown): void {
+  errorContext[key] = value;
+}
+
+/**
+ * The fields that are common to every error report.
+ */
+function environmentFields(): Pick<
+  FrontendErrorPayload,
+  "currentUrl" | "timestamp" | "userAgent" | "viewport" | "breadcrumbs" | "context"
+> {
+  return {
+    currentUrl: globalThis.location.href,
+    timestamp: new Date().toISOString(),
+    userAgent: globalThis.navigator.userAgent,
+    viewport: { width: globalThis.innerWidth, height: globalThis.innerHeight },
+    breadcrumbs: [...breadcrumbs],
+    context: { ...errorContext },
+  };
+}
+
 /**
  * Reports a frontend error to the server.
  */
@@ -50,8 +117,9 @@ export function initGlobalErrorHandlers(): void {
 
       message: String(message),
       stackTrace: error?.stack || null,
-      currentUrl: globalThis.location.href,
-      timestamp: new Date().toString(),
+      severity: "error",
+      name: error?.name || null,
+      ...environmentFields(),
     };
     reportError(errorReport);
     return false;
@@ -67,8 +135,9 @@ export function initGlobalErrorHandlers(): void {
 
       message: error instanceof Error ? error.message : String(error),
       stackTrace: error instanceof Error ? error.stack || null : null,
-      currentUrl: globalThis.location.href,
-      timestamp: new Date().toString(),
+      severity: "error",
+      name: error instanceof Error ? error.name : null,
+      ...environmentFields(),
     };
     reportError(errorReport);
   });
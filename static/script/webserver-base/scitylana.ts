@@ -4,28 +4,67 @@
  */
 
 /**
- * Publishes a highly-opinionated custom analytics event to the server.
+ * Custom properties of an analytics event (only scalar values are accepted).
  */
-async function scitylana(e: Event): Promise<void> {
-  e.preventDefault();
+export type ScitylanaProps = Record<string, string | number | boolean>;
+
+/**
+ * Revenue attached to an analytics event.
+ */
+export type ScitylanaRevenue = {
+  /** ISO 4217 currency code (e.g. "USD"). */
+  currency: string;
+  /** Amount in the given currency (e.g. "12.99"). */
+  amount: string;
+};
+
+/**
+ * Publishes a highly-opinionated analytics event to the server.
+ *
+ * @param name Name of the event (e.g. "pageview", "signup").
+ * @param props Custom properties of the event.
+ * @param revenue Revenue attached to the event.
+ */
+export async function publishScitylanaEvent(
+  name: string,
+  props?: ScitylanaProps,
+  revenue?: ScitylanaRevenue,
+): Promise<void> {
+  const body: URLSearchParams = new URLSearchParams({
+    "user_agent": navigator.userAgent,
+    "url": globalThis.location.href,
+    "referrer": document.referrer,
+    "screen_width": globalThis.innerWidth.toString(),
+    "name": name,
+  });
+  if (props) {
+    body.set("props", JSON.stringify(props));
+  }
+  if (revenue) {
+    body.set("revenue_currency", revenue.currency);
+    body.set("revenue_amount", revenue.amount);
+  }
 
   const response: Response = await fetch("/api/v1/scitylana", {
     method: "POST",
     headers: {
       "Content-Type": "application/x-www-form-urlencoded;charset=UTF-8",
     },
-    body: new URLSearchParams({
-      "user_agent": navigator.userAgent,
-      "url": globalThis.location.href,
-      "referrer": document.referrer,
-      "screen_width": globalThis.innerWidth.toString(),
-    }),
+    body,
   });
   if (!response.ok) {
     console.error("Failed scitylana: ", response);
   }
 }
 
+/**
+ * Publishes a pageview event to the server.
+ */
+async function scitylana(e: Event): Promise<void> {
+  e.preventDefault();
+  await publishScitylanaEvent("pageview");
+}
+
 /**
  * Configures the analytics event to be sent on page load.
  */
@@ -0,0 +1,5 @@
+//! Captures the build information that `webserver_base::build_info!()` reads at compile time.
+
+fn main() {
+    webserver_base_build::emit();
+}
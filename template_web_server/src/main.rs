@@ -1,43 +1,43 @@
+use axum::Router;
+use axum::extract::State;
 use axum::handler::HandlerWithoutStateExt;
-use axum::http::{HeaderMap, StatusCode};
 use axum::response::{Html, IntoResponse, Redirect, Response};
-use axum::routing::{get, post};
-use axum::{Form, Router, serve};
-use axum::{
-    Json,
-    extract::{ConnectInfo, DefaultBodyLimit, State},
-};
+use axum::routing::get;
 use axum_extra::routing::RouterExt;
 use chrono::{DateTime, Utc};
 use reqwest::Client;
-use sentry::ClientInitGuard;
-use sentry::integrations::tower::NewSentryLayer;
 use sitemap_rs::image::Image;
 use sitemap_rs::url::{ChangeFrequency, DEFAULT_PRIORITY, Url};
 use sitemap_rs::url_builder::UrlBuilder;
 use sitemap_rs::url_set::UrlSet;
+use std::env;
 use std::fs::File;
-use std::io::BufWriter;
-use std::net::{IpAddr, SocketAddr};
-use std::str::FromStr;
+use std::io::{self, BufWriter};
 use std::sync::Arc;
+use std::time::Duration;
 use template_web_server::template_data::TemplateData;
 use template_web_server::webserver_error::WebserverResult;
-use tokio::net::TcpListener;
-use tower::ServiceBuilder;
-use tower_http::LatencyUnit;
 use tower_http::services::{ServeDir, ServeFile};
-use tower_http::trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer};
-use tracing::{Level, info, instrument};
-use tracing_subscriber::EnvFilter;
-use tracing_subscriber::layer::SubscriberExt;
-use tracing_subscriber::util::SubscriberInitExt;
+use tracing::{info, instrument};
 use webserver_base::{
-    axum_plausible_analytics::{AxumPlausibleAnalyticsHandler, RequestPayload},
-    base_settings::BaseSettings,
+    api_router::{
+        HasAnalytics, HasBuildInfo, HasFrontendErrorReporter, HasHealthChecks, HasReadiness,
+        HasSettings, api_router,
+    },
+    axum_plausible_analytics::{
+        AnalyticsQueueConfig, AxumPlausibleAnalyticsHandler, PageviewMiddleware,
+        analytics_sink_for_environment,
+    },
+    base_settings::{BaseSettings, SettingsLoader},
+    build_info,
+    build_info::BuildInfo,
     cache_buster::CacheBuster,
-    frontend_error_logger::FrontendErrorPayload,
+    frontend_error_logger::{FrontendErrorReporter, SourceMapResolver},
+    health::{AnalyticsQueueCheck, CacheBusterCheck, HealthChecks, TemplateRegistryCheck},
+    metrics::Metrics,
+    server::Readiness,
     templates::{schema::page::Page, template_registry::TemplateRegistry},
+    web_server::{WebApp, WebServer},
 };
 
 #[derive(Clone)]
@@ -47,6 +47,46 @@ struct AppState {
     template_registry: TemplateRegistry<'static>,
     template_data: TemplateData,
     plausible_client: Arc<AxumPlausibleAnalyticsHandler>,
+    frontend_error_reporter: Arc<FrontendErrorReporter>,
+    readiness: Readiness,
+    health_checks: HealthChecks,
+    build_info: BuildInfo,
+}
+
+impl HasSettings for AppState {
+    fn settings(&self) -> &BaseSettings {
+        &self.settings
+    }
+}
+
+impl HasAnalytics for AppState {
+    fn analytics(&self) -> &Arc<AxumPlausibleAnalyticsHandler> {
+        &self.plausible_client
+    }
+}
+
+impl HasFrontendErrorReporter for AppState {
+    fn frontend_error_reporter(&self) -> &FrontendErrorReporter {
+        &self.frontend_error_reporter
+    }
+}
+
+impl HasReadiness for AppState {
+    fn readiness(&self) -> &Readiness {
+        &self.readiness
+    }
+}
+
+impl HasHealthChecks for AppState {
+    fn health_checks(&self) -> &HealthChecks {
+        &self.health_checks
+    }
+}
+
+impl HasBuildInfo for AppState {
+    fn build_info(&self) -> &BuildInfo {
+        &self.build_info
+    }
 }
 
 impl AppState {
@@ -58,73 +98,98 @@ impl AppState {
         // generate CacheBuster (must occur after sitemap generation)
         let mut cache_buster: CacheBuster = CacheBuster::new("static");
         cache_buster.gen_cache();
+        if cache_buster.asset_count() == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no static assets found in 'static'",
+            )
+            .into());
+        }
         cache_buster.update_source_map_references();
         info!("{}", cache_buster);
         cache_buster.print_to_file("..");
 
+        let build_info: BuildInfo = build_info!();
+        info!("{build_info}");
+
+        // fail at startup rather than serve a site that cannot render any page
+        let template_registry: TemplateRegistry<'static> = TemplateRegistry::default();
+        if template_registry.template_count() == 0 {
+            return Err(
+                io::Error::new(io::ErrorKind::NotFound, "no templates found in 'html'").into(),
+            );
+        }
+        let analytics_queue_config: AnalyticsQueueConfig = AnalyticsQueueConfig::default();
+        let plausible_client: Arc<AxumPlausibleAnalyticsHandler> = Arc::new(
+            AxumPlausibleAnalyticsHandler::new_with_sink(
+                analytics_sink_for_environment(&settings.environment, Client::new()),
+                settings.ip_anonymization,
+                analytics_queue_config,
+            )
+            // the server-side pageview middleware and the `scitylana.ts` beacon both record pageviews
+            .dedup_pageviews(Duration::from_secs(30)),
+        );
+
+        // reported by `/api/v1/health/ready`
+        let health_checks: HealthChecks = HealthChecks::new()
+            // verified above; reported so that the endpoint covers every subsystem
+            .informational(TemplateRegistryCheck::new(&template_registry))
+            .informational(CacheBusterCheck::new(&cache_buster))
+            // report degraded well before the queue fills up and starts dropping events
+            .informational(AnalyticsQueueCheck::new(
+                Arc::clone(&plausible_client),
+                analytics_queue_config.capacity / 2,
+            ));
+
         Ok(Self {
             settings: settings.clone(),
             cache_buster: cache_buster.clone(),
-            template_registry: TemplateRegistry::default(),
-            template_data: TemplateData::new(settings.clone(), &cache_buster),
-            plausible_client: Arc::new(AxumPlausibleAnalyticsHandler::new_with_client(
-                Client::new(),
+            template_registry,
+            template_data: TemplateData::new(settings.clone(), &cache_buster, build_info.clone()),
+            plausible_client,
+            frontend_error_reporter: Arc::new(FrontendErrorReporter::new(
+                settings.clone(),
+                SourceMapResolver::new(&cache_buster),
             )),
+            readiness: Readiness::new(),
+            health_checks,
+            build_info,
         })
     }
 }
 
 #[instrument(skip_all)]
 fn main() {
-    // env vars
-    let settings: BaseSettings = BaseSettings::default();
+    // settings (env vars, overridden by command-line arguments)
+    let settings: BaseSettings = SettingsLoader::new()
+        .args(env::args().skip(1))
+        .load()
+        .unwrap_or_else(|e| panic!("failed to load settings:\n{e}"));
 
-    // sentry
-    let _guard: ClientInitGuard = sentry::init((
-        settings.sentry_dsn.clone(),
-        sentry::ClientOptions {
-            release: sentry::release_name!(),
-            attach_stacktrace: true,
-            ..Default::default()
-        },
-    ));
-
-    // Must manually create a multithreaded tokio runtime so that the Sentry hub will be applied to all threads
-    tokio::runtime::Builder::new_multi_thread()
-        .enable_all()
-        .build()
-        .unwrap()
-        .block_on(async_main(settings))
+    WebServer::new(settings)
+        .sentry_options(|options| options.release = sentry::release_name!())
+        .run(app)
         .unwrap();
 }
 
 #[instrument(skip_all)]
-async fn async_main(settings: BaseSettings) -> WebserverResult<()> {
-    // initialize tracing
-    tracing_subscriber::Registry::default()
-        .with(
-            EnvFilter::try_from_default_env()
-                .or_else(|_| EnvFilter::try_new("info"))
-                .unwrap(),
-        )
-        .with(tracing_subscriber::fmt::Layer::default())
-        .with(sentry::integrations::tracing::layer())
-        .init();
-
+async fn app(settings: BaseSettings) -> WebserverResult<WebApp> {
     // app state
     let app_state: AppState = AppState::new(&settings)?;
+    let plausible_client: Arc<AxumPlausibleAnalyticsHandler> =
+        Arc::clone(&app_state.plausible_client);
+    let readiness: Readiness = app_state.readiness.clone();
 
-    let no_cache_routes: Router<Arc<AppState>> = Router::new()
+    // metrics (served at `/metrics`)
+    let metrics: Metrics = Metrics::new();
+    metrics.record_cache_buster(&app_state.cache_buster);
+    metrics.track_analytics(Arc::clone(&plausible_client));
+    metrics.track_frontend_errors(Arc::clone(&app_state.frontend_error_reporter));
+
+    let mut no_cache_routes: Router<Arc<AppState>> = Router::new()
         .route("/", get(home))
         .route_with_tsr("/404", get(four_oh_four))
-        .nest(
-            "/api/v1",
-            Router::new()
-                .route_with_tsr("/health", get(health_check))
-                .route_with_tsr("/scitylana", post(analytics))
-                .route_with_tsr("/frontend-error", post(frontend_error))
-                .fallback(fallback),
-        )
+        .merge(api_router::<AppState>())
         .nest_service(
             "/favicon.ico",
             ServeFile::new(
@@ -148,56 +213,40 @@ async fn async_main(settings: BaseSettings) -> WebserverResult<()> {
         .layer(axum::middleware::from_fn(
             CacheBuster::never_cache_middleware,
         ));
+    if settings.reported_analytics_domain().is_some() {
+        no_cache_routes = no_cache_routes.layer(axum::middleware::from_fn_with_state(
+            Arc::new(PageviewMiddleware::new(
+                Arc::clone(&plausible_client),
+                settings.clone(),
+            )),
+            PageviewMiddleware::middleware,
+        ));
+    }
 
     let forever_cache_routes: Router<Arc<AppState>> = Router::new()
         .nest_service(
             "/static",
             ServeDir::new("static").fallback(fallback.into_service()),
         )
-        .layer(axum::middleware::from_fn(
-            CacheBuster::forever_cache_middleware,
+        .layer(axum::middleware::from_fn_with_state(
+            settings.profile().static_cache_policy,
+            CacheBuster::cache_policy_middleware,
         ));
 
     // build our application with a route
-    let app: Router = Router::new()
+    let router: Router = Router::new()
         .nest("", no_cache_routes)
         .nest("", forever_cache_routes)
         .fallback(fallback)
-        .with_state(Arc::new(app_state))
-        .layer(
-            ServiceBuilder::new()
-                .layer(DefaultBodyLimit::max(1024))
-                .layer(
-                    TraceLayer::new_for_http()
-                        .make_span_with(
-                            DefaultMakeSpan::default()
-                                .level(Level::INFO)
-                                .include_headers(false),
-                        )
-                        .on_response(
-                            DefaultOnResponse::new()
-                                .level(Level::INFO)
-                                .latency_unit(LatencyUnit::Micros),
-                        ),
-                )
-                .layer(NewSentryLayer::new_from_top()),
-        );
-
-    // run it
-    let addr: SocketAddr = SocketAddr::new(
-        IpAddr::from_str(settings.host.as_str()).expect("failed to parse host"),
-        settings.port,
-    );
-    info!("listening on {}", addr);
-    let listener: TcpListener = TcpListener::bind(&addr).await.unwrap();
-    serve(
-        listener,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
-    )
-    .await
-    .unwrap();
+        .with_state(Arc::new(app_state));
 
-    Ok(())
+    Ok(WebApp::new(router)
+        .readiness(readiness)
+        .metrics(metrics)
+        // send any analytics events that are still queued
+        .on_shutdown("analytics", move || async move {
+            plausible_client.shutdown(Duration::from_secs(2)).await;
+        }))
 }
 
 #[instrument(skip_all)]
@@ -241,29 +290,6 @@ async fn fallback() -> Response {
     Redirect::to("/404").into_response()
 }
 
-async fn health_check() -> StatusCode {
-    StatusCode::OK
-}
-
-#[instrument(skip_all)]
-async fn analytics(
-    headers: HeaderMap,
-    State(state): State<Arc<AppState>>,
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    Form(incoming_payload): Form<RequestPayload>,
-) -> StatusCode {
-    let plausible_client: Arc<AxumPlausibleAnalyticsHandler> = Arc::clone(&state.plausible_client);
-    plausible_client
-        .handle(headers, state.settings.clone(), addr, incoming_payload)
-        .await
-}
-
-#[instrument(skip_all)]
-async fn frontend_error(Json(frontend_error_payload): Json<FrontendErrorPayload>) -> StatusCode {
-    frontend_error_payload.log();
-    StatusCode::OK
-}
-
 #[instrument(skip_all)]
 fn generate_sitemaps(settings: &BaseSettings) -> WebserverResult<()> {
     // track all the base routes (e.g. "/blog", "/projects", etc.)
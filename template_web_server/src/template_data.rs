@@ -3,6 +3,7 @@ use std::{collections::BTreeMap, fmt::Display};
 use serde::{Deserialize, Serialize};
 use webserver_base::{
     base_settings::BaseSettings,
+    build_info::BuildInfo,
     cache_buster::CacheBuster,
     templates::schema::{
         copyright::Copyright, footer::Footer, metadata::Metadata, page::Page,
@@ -19,11 +20,13 @@ pub struct TemplateData {
     social_media: Vec<SocialMedia>,
 
     cache_buster: BTreeMap<String, String>,
+
+    build: BuildInfo,
 }
 
 impl TemplateData {
     #[must_use]
-    pub fn new(settings: BaseSettings, cache_buster: &CacheBuster) -> Self {
+    pub fn new(settings: BaseSettings, cache_buster: &CacheBuster, build_info: BuildInfo) -> Self {
         let social_media: Vec<SocialMedia> = vec![
             SocialMedia::new("Twitter", "https://twitter.com/goddtriffin"),
             SocialMedia::new("Instagram", "https://www.instagram.com/goddtriffin/"),
@@ -67,6 +70,7 @@ impl TemplateData {
             page: None,
             social_media,
             cache_buster: cache_buster.get_cache(),
+            build: build_info,
         }
     }
 
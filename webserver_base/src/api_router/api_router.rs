@@ -0,0 +1,141 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::extract::{ConnectInfo, DefaultBodyLimit, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::routing::{get, post};
+use axum::{Form, Json, Router};
+use axum_extra::routing::RouterExt;
+use serde_json::{Value, json};
+use tracing::instrument;
+
+use super::{
+    HasAnalytics, HasBuildInfo, HasFrontendErrorReporter, HasHealthChecks, HasReadiness,
+    HasSettings,
+};
+use crate::axum_plausible_analytics::{ANALYTICS_BODY_LIMIT, RequestPayload};
+use crate::build_info::BuildInfo;
+use crate::frontend_error_logger::{FRONTEND_ERROR_BODY_LIMIT, FrontendErrorPayload};
+use crate::health::{HealthReport, HealthStatus};
+
+/// The `/api/v1` endpoints that every site serves, ready to be `.merge()`d into the site's router:
+///
+/// - `GET /api/v1/health` (`503 Service Unavailable` while starting up or shutting down)
+/// - `GET /api/v1/health/live` (`200 OK` while the process can serve requests at all)
+/// - `GET /api/v1/health/ready` (`HealthReport` of every `HealthCheck`; `503 Service Unavailable` while starting up,
+///   shutting down, or if a check registered with `HealthChecks::check` fails)
+/// - `GET /api/v1/version` (the running `BuildInfo`)
+/// - `POST /api/v1/scitylana` (analytics events from `scitylana.ts`)
+/// - `POST /api/v1/frontend-error` (error reports from `frontend-error.ts`)
+///
+/// Unknown `/api/v1` routes redirect to `/404`.
+///
+/// The server must be served with `into_make_service_with_connect_info::<SocketAddr>()`.
+pub fn api_router<S>() -> Router<Arc<S>>
+where
+    S: HasSettings
+        + HasAnalytics
+        + HasFrontendErrorReporter
+        + HasReadiness
+        + HasHealthChecks
+        + HasBuildInfo
+        + Send
+        + Sync
+        + 'static,
+{
+    Router::new().nest(
+        "/api/v1",
+        Router::new()
+            .route_with_tsr("/health", get(health_check::<S>))
+            .route_with_tsr("/health/live", get(health_live))
+            .route_with_tsr("/health/ready", get(health_ready::<S>))
+            .route_with_tsr("/version", get(version::<S>))
+            .route_with_tsr(
+                "/scitylana",
+                post(analytics::<S>).layer(DefaultBodyLimit::max(ANALYTICS_BODY_LIMIT)),
+            )
+            .route_with_tsr(
+                "/frontend-error",
+                post(frontend_error::<S>).layer(DefaultBodyLimit::max(FRONTEND_ERROR_BODY_LIMIT)),
+            )
+            .fallback(api_fallback),
+    )
+}
+
+pub async fn health_check<S>(State(state): State<Arc<S>>) -> StatusCode
+where
+    S: HasReadiness + Send + Sync + 'static,
+{
+    if state.readiness().is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+/// Liveness: answers as long as the server can handle requests, whether or not it is ready for traffic.
+pub async fn health_live() -> Json<Value> {
+    Json(json!({ "status": HealthStatus::Pass }))
+}
+
+/// Readiness: runs the registered `HealthCheck`s and reports each one's status and latency.
+#[instrument(skip_all)]
+pub async fn health_ready<S>(State(state): State<Arc<S>>) -> (StatusCode, Json<HealthReport>)
+where
+    S: HasReadiness + HasHealthChecks + Send + Sync + 'static,
+{
+    let report: HealthReport = state
+        .health_checks()
+        .run(state.readiness().is_ready())
+        .await;
+    let status_code: StatusCode = match report.status {
+        HealthStatus::Pass | HealthStatus::Warn => StatusCode::OK,
+        HealthStatus::Fail => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status_code, Json(report))
+}
+
+#[instrument(skip_all)]
+pub async fn version<S>(State(state): State<Arc<S>>) -> Json<BuildInfo>
+where
+    S: HasBuildInfo + Send + Sync + 'static,
+{
+    Json(state.build_info().clone())
+}
+
+#[instrument(skip_all)]
+pub async fn analytics<S>(
+    headers: HeaderMap,
+    State(state): State<Arc<S>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Form(incoming_payload): Form<RequestPayload>,
+) -> (StatusCode, String)
+where
+    S: HasSettings + HasAnalytics + Send + Sync + 'static,
+{
+    Arc::clone(state.analytics())
+        .handle(headers, state.settings().clone(), addr, incoming_payload)
+        .await
+}
+
+#[instrument(skip_all)]
+pub async fn frontend_error<S>(
+    headers: HeaderMap,
+    State(state): State<Arc<S>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Json(frontend_error_payload): Json<FrontendErrorPayload>,
+) -> StatusCode
+where
+    S: HasFrontendErrorReporter + Send + Sync + 'static,
+{
+    state
+        .frontend_error_reporter()
+        .report(&headers, addr, &frontend_error_payload);
+    StatusCode::OK
+}
+
+#[instrument(skip_all)]
+pub async fn api_fallback() -> Response {
+    Redirect::to("/404").into_response()
+}
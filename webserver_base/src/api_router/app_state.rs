@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use crate::axum_plausible_analytics::AxumPlausibleAnalyticsHandler;
+use crate::base_settings::BaseSettings;
+use crate::build_info::BuildInfo;
+use crate::frontend_error_logger::FrontendErrorReporter;
+use crate::health::HealthChecks;
+use crate::server::Readiness;
+
+/// App state that exposes the server's settings.
+pub trait HasSettings {
+    fn settings(&self) -> &BaseSettings;
+}
+
+/// App state that exposes the analytics handler used by the `/api/v1/scitylana` endpoint.
+pub trait HasAnalytics {
+    fn analytics(&self) -> &Arc<AxumPlausibleAnalyticsHandler>;
+}
+
+/// App state that exposes the reporter used by the `/api/v1/frontend-error` endpoint.
+pub trait HasFrontendErrorReporter {
+    fn frontend_error_reporter(&self) -> &FrontendErrorReporter;
+}
+
+/// App state that exposes the readiness reported by the `/api/v1/health` and `/api/v1/health/ready` endpoints.
+pub trait HasReadiness {
+    fn readiness(&self) -> &Readiness;
+}
+
+/// App state that exposes the checks run by the `/api/v1/health/ready` endpoint.
+pub trait HasHealthChecks {
+    fn health_checks(&self) -> &HealthChecks;
+}
+
+/// App state that exposes the build reported by the `/api/v1/version` endpoint.
+pub trait HasBuildInfo {
+    fn build_info(&self) -> &BuildInfo;
+}
@@ -0,0 +1,6 @@
+#[expect(clippy::module_inception)]
+mod api_router;
+mod app_state;
+
+pub use api_router::*;
+pub use app_state::*;
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use plausible_rs::{PAGEVIEW_EVENT, PropValue};
+use serde::{Deserialize, Serialize};
+
+/// Revenue attached to a Plausible Analytics event.
+///
+/// See: <https://plausible.io/docs/ecommerce-revenue-tracking>
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Revenue {
+    /// ISO 4217 currency code (e.g. "USD").
+    pub currency: String,
+
+    /// Amount in the given currency (e.g. "12.99").
+    pub amount: String,
+}
+
+impl Revenue {
+    #[must_use]
+    pub const fn new(currency: String, amount: String) -> Self {
+        Self { currency, amount }
+    }
+}
+
+/// A pageview or custom event to record in Plausible Analytics.
+///
+/// Server-side handlers can build these directly (e.g. "signup", "download") and fire them through
+/// `AxumPlausibleAnalyticsHandler::track` without a browser round-trip.
+#[derive(Debug, Clone)]
+pub struct AnalyticsEvent {
+    pub name: String,
+    pub url: String,
+    pub referrer: Option<String>,
+    pub screen_width: Option<usize>,
+    pub props: HashMap<String, PropValue>,
+    pub revenue: Option<Revenue>,
+}
+
+impl AnalyticsEvent {
+    #[must_use]
+    pub fn new(name: &str, url: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            url: url.to_string(),
+            referrer: None,
+            screen_width: None,
+            props: HashMap::new(),
+            revenue: None,
+        }
+    }
+
+    #[must_use]
+    pub fn pageview(url: &str) -> Self {
+        Self::new(PAGEVIEW_EVENT, url)
+    }
+
+    #[must_use]
+    pub fn referrer(mut self, referrer: String) -> Self {
+        self.referrer = Some(referrer);
+        self
+    }
+
+    #[must_use]
+    pub const fn screen_width(mut self, screen_width: usize) -> Self {
+        self.screen_width = Some(screen_width);
+        self
+    }
+
+    #[must_use]
+    pub fn prop(mut self, key: &str, value: impl Into<PropValue>) -> Self {
+        self.props.insert(key.to_string(), value.into());
+        self
+    }
+
+    #[must_use]
+    pub fn revenue(mut self, revenue: Revenue) -> Self {
+        self.revenue = Some(revenue);
+        self
+    }
+}
@@ -0,0 +1,240 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::{Notify, mpsc};
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{error, info, instrument, warn};
+
+use super::{AnalyticsError, AnalyticsSink, QueuedEvent};
+
+/// Tuning for the background worker that forwards analytics events to an `AnalyticsSink`.
+#[derive(Debug, Clone, Copy)]
+pub struct AnalyticsQueueConfig {
+    /// Maximum number of events waiting to be sent. Events that arrive while the queue is full are dropped.
+    pub capacity: usize,
+
+    /// Maximum number of events sent concurrently by the worker.
+    pub batch_size: usize,
+
+    /// Number of times a failed call is retried (server errors, rate limits and network errors only).
+    pub max_retries: u32,
+
+    /// Delay before the first retry; doubled for every retry after that.
+    pub initial_backoff: Duration,
+}
+
+impl Default for AnalyticsQueueConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            batch_size: 32,
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+/// What happened to the analytics events since the server started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AnalyticsStats {
+    /// Events the sink accepted.
+    pub forwarded: u64,
+
+    /// Events the sink rejected, or that still failed after every retry.
+    pub failed: u64,
+
+    /// Events dropped because the queue was full or shut down.
+    pub dropped: u64,
+
+    /// Events waiting to be sent.
+    pub queued: usize,
+}
+
+#[derive(Debug, Default)]
+struct AnalyticsCounters {
+    forwarded: AtomicU64,
+    failed: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// Bounded queue of analytics events, drained by a background worker.
+pub(crate) struct AnalyticsQueue {
+    sender: mpsc::Sender<QueuedEvent>,
+    shutdown: Arc<Notify>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+    counters: Arc<AnalyticsCounters>,
+}
+
+impl AnalyticsQueue {
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    #[instrument(skip_all)]
+    pub fn spawn(sink: Arc<dyn AnalyticsSink>, config: AnalyticsQueueConfig) -> Self {
+        let (sender, receiver) = mpsc::channel(config.capacity.max(1));
+        let shutdown: Arc<Notify> = Arc::new(Notify::new());
+        let counters: Arc<AnalyticsCounters> = Arc::new(AnalyticsCounters::default());
+
+        let worker: JoinHandle<()> = tokio::spawn(run_worker(
+            receiver,
+            Arc::clone(&shutdown),
+            sink,
+            config,
+            Arc::clone(&counters),
+        ));
+
+        Self {
+            sender,
+            shutdown,
+            worker: Mutex::new(Some(worker)),
+            counters,
+        }
+    }
+
+    pub fn stats(&self) -> AnalyticsStats {
+        AnalyticsStats {
+            forwarded: self.counters.forwarded.load(Ordering::Relaxed),
+            failed: self.counters.failed.load(Ordering::Relaxed),
+            dropped: self.counters.dropped.load(Ordering::Relaxed),
+            queued: self.sender.max_capacity() - self.sender.capacity(),
+        }
+    }
+
+    /// Enqueues an event without waiting for it to be sent.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the queue is full or has been shut down (the event is dropped).
+    #[instrument(skip_all)]
+    pub fn enqueue(&self, event: QueuedEvent) -> Result<(), AnalyticsError> {
+        self.sender.try_send(event).map_err(|e| {
+            self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+            match e {
+                mpsc::error::TrySendError::Full(event) => {
+                    warn!(
+                        "analytics queue is full; dropping '{}' event",
+                        event.body.name
+                    );
+                    AnalyticsError::QueueFull
+                }
+                mpsc::error::TrySendError::Closed(event) => {
+                    warn!(
+                        "analytics queue is closed; dropping '{}' event",
+                        event.body.name
+                    );
+                    AnalyticsError::QueueClosed
+                }
+            }
+        })
+    }
+
+    /// Stops accepting events and waits (up to `timeout`) for the worker to send everything still queued.
+    #[instrument(skip_all)]
+    pub async fn flush(&self, timeout: Duration) {
+        self.shutdown.notify_one();
+
+        let worker: Option<JoinHandle<()>> = self
+            .worker
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .take();
+        let Some(worker) = worker else {
+            return;
+        };
+
+        match tokio::time::timeout(timeout, worker).await {
+            Ok(Ok(())) => info!("flushed analytics queue"),
+            Ok(Err(e)) => error!("analytics worker failed while flushing: {e}"),
+            Err(_) => warn!("timed out after {timeout:?} while flushing analytics queue"),
+        }
+    }
+}
+
+#[instrument(skip_all)]
+async fn run_worker(
+    mut receiver: mpsc::Receiver<QueuedEvent>,
+    shutdown: Arc<Notify>,
+    sink: Arc<dyn AnalyticsSink>,
+    config: AnalyticsQueueConfig,
+    counters: Arc<AnalyticsCounters>,
+) {
+    let batch_size: usize = config.batch_size.max(1);
+    let mut batch: Vec<QueuedEvent> = Vec::with_capacity(batch_size);
+
+    loop {
+        tokio::select! {
+            count = receiver.recv_many(&mut batch, batch_size) => {
+                if count == 0 {
+                    break;
+                }
+                dispatch_batch(&sink, config, &counters, &mut batch).await;
+            }
+            () = shutdown.notified() => {
+                // stop accepting new events, then drain whatever is left
+                receiver.close();
+                while receiver.recv_many(&mut batch, batch_size).await > 0 {
+                    dispatch_batch(&sink, config, &counters, &mut batch).await;
+                }
+                break;
+            }
+        }
+    }
+}
+
+#[instrument(skip_all)]
+async fn dispatch_batch(
+    sink: &Arc<dyn AnalyticsSink>,
+    config: AnalyticsQueueConfig,
+    counters: &Arc<AnalyticsCounters>,
+    batch: &mut Vec<QueuedEvent>,
+) {
+    let mut calls: JoinSet<()> = JoinSet::new();
+    for event in batch.drain(..) {
+        calls.spawn(send_event_with_retries(
+            Arc::clone(sink),
+            config,
+            Arc::clone(counters),
+            event,
+        ));
+    }
+    while calls.join_next().await.is_some() {}
+}
+
+#[instrument(skip_all)]
+async fn send_event_with_retries(
+    sink: Arc<dyn AnalyticsSink>,
+    config: AnalyticsQueueConfig,
+    counters: Arc<AnalyticsCounters>,
+    event: QueuedEvent,
+) {
+    let mut backoff: Duration = config.initial_backoff;
+    let mut attempt: u32 = 0;
+    loop {
+        match sink.send(&event).await {
+            Ok(()) => {
+                counters.forwarded.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+            Err(e) if attempt < config.max_retries && e.is_retryable() => {
+                warn!(
+                    "analytics sink '{}' failed to send '{}' event (retrying in {backoff:?}): {e}",
+                    sink.name(),
+                    event.body.name
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = backoff.saturating_mul(2);
+                attempt += 1;
+            }
+            Err(e) => {
+                error!(
+                    "analytics sink '{}' failed to send '{}' event: {e}",
+                    sink.name(),
+                    event.body.name
+                );
+                counters.failed.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use axum::http::StatusCode;
+use chrono::Utc;
+use plausible_rs::{BASE_URL, EventHeaders};
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::{Value, json};
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info, instrument};
+
+use super::{AnalyticsError, Revenue};
+use crate::base_settings::Environment;
+
+/// Request body for Plausible's 'POST /api/event' API.
+///
+/// `plausible_rs::EventPayload` has no `revenue` field (and serializes `PropValue`s as tagged enums), so the body is
+/// built here instead.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlausibleEventBody {
+    pub domain: String,
+    pub name: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub referrer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub screen_width: Option<usize>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub props: HashMap<String, Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revenue: Option<Revenue>,
+}
+
+/// An analytics event that is ready to be handed to an `AnalyticsSink`.
+#[derive(Debug, Clone)]
+pub struct QueuedEvent {
+    pub headers: EventHeaders,
+    pub body: PlausibleEventBody,
+}
+
+pub type AnalyticsSinkFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<(), AnalyticsError>> + Send + 'a>>;
+
+/// Destination for analytics events.
+///
+/// The queue worker hands every event to exactly one sink, and retries the call if it fails with a retryable
+/// `AnalyticsError`.
+pub trait AnalyticsSink: Send + Sync {
+    /// Short name of the sink, used in logs.
+    fn name(&self) -> &'static str;
+
+    fn send<'a>(&'a self, event: &'a QueuedEvent) -> AnalyticsSinkFuture<'a>;
+}
+
+/// Picks the sink for an environment: production and staging talk to Plausible; development writes to a local
+/// `analytics.jsonl` file so it never hits the network, and tests discard events.
+#[must_use]
+#[instrument(skip_all)]
+pub fn analytics_sink_for_environment(
+    environment: &Environment,
+    http_client: Client,
+) -> Arc<dyn AnalyticsSink> {
+    match environment {
+        Environment::Production | Environment::Staging => Arc::new(PlausibleSink::new(http_client)),
+        Environment::Development => Arc::new(FileSink::new(PathBuf::from("analytics.jsonl"))),
+        Environment::Test => Arc::new(NoopSink),
+    }
+}
+
+/// Sends events to Plausible Analytics.
+#[derive(Debug, Clone)]
+pub struct PlausibleSink {
+    http_client: Client,
+}
+
+impl PlausibleSink {
+    #[must_use]
+    pub const fn new(http_client: Client) -> Self {
+        Self { http_client }
+    }
+
+    #[instrument(skip_all)]
+    async fn post_event(&self, event: &QueuedEvent) -> Result<(), AnalyticsError> {
+        let response = self
+            .http_client
+            .post(format!("{BASE_URL}/api/event"))
+            .header("User-Agent", event.headers.user_agent.clone())
+            .header("X-Forwarded-For", event.headers.x_forwarded_for.clone())
+            .json(&event.body)
+            .send()
+            .await
+            .map_err(plausible_rs::Error::from)?;
+
+        let status_code: StatusCode = response.status();
+        let bytes = response.bytes().await.map_err(plausible_rs::Error::from)?;
+        if !status_code.is_success() {
+            return Err(plausible_rs::Error::RequestFailed { bytes, status_code }.into());
+        }
+
+        info!(
+            "Plausible Analytics '{}' call was a success: {}",
+            event.body.name,
+            String::from_utf8_lossy(&bytes)
+        );
+        Ok(())
+    }
+}
+
+impl AnalyticsSink for PlausibleSink {
+    fn name(&self) -> &'static str {
+        "plausible"
+    }
+
+    fn send<'a>(&'a self, event: &'a QueuedEvent) -> AnalyticsSinkFuture<'a> {
+        Box::pin(self.post_event(event))
+    }
+}
+
+/// Appends events to a JSON-lines file.
+///
+/// Client IP addresses are never written to the file.
+#[derive(Debug)]
+pub struct FileSink {
+    path: PathBuf,
+    lock: tokio::sync::Mutex<()>,
+}
+
+impl FileSink {
+    #[must_use]
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    #[instrument(skip_all)]
+    async fn append(&self, event: &QueuedEvent) -> Result<(), AnalyticsError> {
+        let mut line: String = json!({
+            "timestamp": Utc::now(),
+            "user_agent": event.headers.user_agent,
+            "event": event.body,
+        })
+        .to_string();
+        line.push('\n');
+
+        // serialize writes so lines from concurrent events never interleave
+        let _guard = self.lock.lock().await;
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        Ok(())
+    }
+}
+
+impl AnalyticsSink for FileSink {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn send<'a>(&'a self, event: &'a QueuedEvent) -> AnalyticsSinkFuture<'a> {
+        Box::pin(self.append(event))
+    }
+}
+
+/// Keeps events in memory (for tests).
+#[derive(Debug, Default)]
+pub struct MemorySink {
+    events: Mutex<Vec<QueuedEvent>>,
+}
+
+impl MemorySink {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All events received so far, oldest first.
+    #[must_use]
+    pub fn events(&self) -> Vec<QueuedEvent> {
+        self.events
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
+    }
+}
+
+impl AnalyticsSink for MemorySink {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn send<'a>(&'a self, event: &'a QueuedEvent) -> AnalyticsSinkFuture<'a> {
+        self.events
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .push(event.clone());
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Discards every event.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopSink;
+
+impl AnalyticsSink for NoopSink {
+    fn name(&self) -> &'static str {
+        "noop"
+    }
+
+    fn send<'a>(&'a self, event: &'a QueuedEvent) -> AnalyticsSinkFuture<'a> {
+        debug!("discarding '{}' analytics event", event.body.name);
+        Box::pin(async { Ok(()) })
+    }
+}
@@ -0,0 +1,386 @@
+use axum::http::{HeaderMap, StatusCode, header::USER_AGENT};
+use plausible_rs::{EventHeaders, PAGEVIEW_EVENT, PropValue};
+use reqwest::{Client, Url};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, instrument, warn};
+
+use super::analytics_queue::AnalyticsQueue;
+use super::{
+    AnalyticsError, AnalyticsEvent, AnalyticsQueueConfig, AnalyticsSink, AnalyticsStats,
+    PayloadValidator, PlausibleEventBody, PlausibleSink, QueuedEvent, RequestPayload,
+};
+use crate::{
+    base_settings::BaseSettings,
+    ip::{IpAnonymization, IpAnonymizer, resolve_true_client_ip_address},
+    user_agent::{UserAgentClass, UserAgentClassifier},
+};
+
+/// What to do with analytics events sent by bots (anything not classified as `UserAgentClass::Browser`).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum BotPolicy {
+    /// Forward bot events like any other event.
+    Allow,
+
+    /// Forward bot events with a `user_agent_class` custom property.
+    Tag,
+
+    /// Drop bot events.
+    #[default]
+    Drop,
+}
+
+/// When each recently viewed page (hashed with its client) was viewed, for `dedup_pageviews`.
+#[derive(Debug)]
+struct RecentPageviews {
+    viewed_at: HashMap<u64, Instant>,
+    pruned_at: Instant,
+}
+
+/// Forwards analytics events to Plausible Analytics (or any other `AnalyticsSink`).
+///
+/// Events are queued and sent by a background worker, so handlers never wait on the sink. Call `shutdown` before the
+/// server exits to flush anything still queued.
+pub struct AxumPlausibleAnalyticsHandler {
+    queue: AnalyticsQueue,
+    ip_anonymizer: IpAnonymizer,
+    user_agent_classifier: UserAgentClassifier,
+    bot_policy: BotPolicy,
+    payload_validator: PayloadValidator,
+
+    pageview_dedup_window: Option<Duration>,
+    recent_pageviews: Mutex<RecentPageviews>,
+}
+
+impl AxumPlausibleAnalyticsHandler {
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    #[must_use]
+    #[instrument(skip_all)]
+    pub fn new_with_client(http_client: Client, ip_anonymization: IpAnonymization) -> Self {
+        Self::new_with_config(
+            http_client,
+            ip_anonymization,
+            AnalyticsQueueConfig::default(),
+        )
+    }
+
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    #[must_use]
+    #[instrument(skip_all)]
+    pub fn new_with_config(
+        http_client: Client,
+        ip_anonymization: IpAnonymization,
+        queue_config: AnalyticsQueueConfig,
+    ) -> Self {
+        Self::new_with_sink(
+            Arc::new(PlausibleSink::new(http_client)),
+            ip_anonymization,
+            queue_config,
+        )
+    }
+
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    #[must_use]
+    #[instrument(skip_all)]
+    pub fn new_with_sink(
+        sink: Arc<dyn AnalyticsSink>,
+        ip_anonymization: IpAnonymization,
+        queue_config: AnalyticsQueueConfig,
+    ) -> Self {
+        info!("sending analytics events to the '{}' sink", sink.name());
+        Self {
+            queue: AnalyticsQueue::spawn(sink, queue_config),
+            ip_anonymizer: IpAnonymizer::new(ip_anonymization),
+            user_agent_classifier: UserAgentClassifier::default(),
+            bot_policy: BotPolicy::default(),
+            payload_validator: PayloadValidator::default(),
+            pageview_dedup_window: None,
+            recent_pageviews: Mutex::new(RecentPageviews {
+                viewed_at: HashMap::new(),
+                pruned_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Replaces the default bot filtering (the built-in `UserAgentClassifier` rules with `BotPolicy::Drop`).
+    #[must_use]
+    pub fn bot_filter(
+        mut self,
+        user_agent_classifier: UserAgentClassifier,
+        bot_policy: BotPolicy,
+    ) -> Self {
+        self.user_agent_classifier = user_agent_classifier;
+        self.bot_policy = bot_policy;
+        self
+    }
+
+    /// Replaces the default validation of browser payloads.
+    #[must_use]
+    pub fn payload_validator(mut self, payload_validator: PayloadValidator) -> Self {
+        self.payload_validator = payload_validator;
+        self
+    }
+
+    /// Drops repeated pageviews of the same page by the same client (IP address + User-Agent) within `window` of the
+    /// first one.
+    ///
+    /// Lets the server-side `PageviewMiddleware` coexist with the client-side `scitylana.ts` beacon: whichever arrives
+    /// first is recorded.
+    #[must_use]
+    pub const fn dedup_pageviews(mut self, window: Duration) -> Self {
+        self.pageview_dedup_window = Some(window);
+        self
+    }
+
+    /// How many events were forwarded, failed or dropped so far, and how many are queued.
+    #[must_use]
+    pub fn stats(&self) -> AnalyticsStats {
+        self.queue.stats()
+    }
+
+    /// Stops accepting events and waits (up to `timeout`) for all queued events to be sent.
+    #[instrument(skip_all)]
+    pub async fn shutdown(&self, timeout: Duration) {
+        self.queue.flush(timeout).await;
+    }
+
+    /// Validates, sanitises and queues an analytics payload sent by a browser.
+    ///
+    /// Responds with `202 Accepted` once queued, or a 4xx/5xx status code and reason otherwise (only the status' reason
+    /// phrase, unless the environment has verbose errors). Events dropped because the queue is full (or shut down) are
+    /// still accepted, since the browser cannot do anything about it; they are counted in `AnalyticsStats::dropped`.
+    #[instrument(skip_all)]
+    pub async fn handle(
+        self: Arc<Self>,
+        headers: HeaderMap,
+        settings: BaseSettings,
+        addr: SocketAddr,
+        incoming_payload: RequestPayload,
+    ) -> (StatusCode, String) {
+        // the beacon is served everywhere; don't validate (and warn about) payloads that would be dropped anyway
+        if settings.reported_analytics_domain().is_none() {
+            debug!(
+                "analytics are disabled in the '{}' environment; dropping beacon payload",
+                settings.environment
+            );
+            return (StatusCode::ACCEPTED, String::new());
+        }
+
+        let result: Result<(), AnalyticsError> = self
+            .payload_validator
+            .validate(incoming_payload, &settings)
+            .and_then(|incoming_payload| {
+                let event: AnalyticsEvent = incoming_payload.to_analytics_event();
+                self.send(
+                    &headers,
+                    &settings,
+                    addr,
+                    incoming_payload.user_agent,
+                    event,
+                )
+            });
+
+        match result {
+            // already logged and counted by the queue
+            Ok(()) | Err(AnalyticsError::QueueFull | AnalyticsError::QueueClosed) => {
+                (StatusCode::ACCEPTED, String::new())
+            }
+            Err(e) => {
+                warn!("rejected analytics payload: {e}");
+                let message: String = if settings.profile().verbose_errors {
+                    e.to_string()
+                } else {
+                    e.status_code()
+                        .canonical_reason()
+                        .unwrap_or_default()
+                        .to_string()
+                };
+                (e.status_code(), message)
+            }
+        }
+    }
+
+    /// Records a server-side event (e.g. "signup", "download") on behalf of the client that made the current request.
+    ///
+    /// The client's User-Agent and IP address are taken from the request, so no browser round-trip is required. The
+    /// event is queued, not sent, by the time this returns.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the event could not be queued.
+    #[instrument(skip_all)]
+    pub fn track(
+        &self,
+        headers: &HeaderMap,
+        settings: &BaseSettings,
+        addr: SocketAddr,
+        event: AnalyticsEvent,
+    ) -> Result<(), AnalyticsError> {
+        let user_agent: String = headers
+            .get(USER_AGENT)
+            .and_then(|user_agent| user_agent.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+
+        self.send(headers, settings, addr, user_agent, event)
+    }
+
+    #[instrument(skip_all)]
+    fn send(
+        &self,
+        headers: &HeaderMap,
+        settings: &BaseSettings,
+        addr: SocketAddr,
+        user_agent: String,
+        mut event: AnalyticsEvent,
+    ) -> Result<(), AnalyticsError> {
+        let Some(domain) = settings.reported_analytics_domain() else {
+            debug!(
+                "analytics are disabled in the '{}' environment; dropping '{}' event",
+                settings.environment, event.name
+            );
+            return Ok(());
+        };
+
+        // filter bots
+        let user_agent_class: UserAgentClass = self.user_agent_classifier.classify(&user_agent);
+        if user_agent_class.is_bot() {
+            match self.bot_policy {
+                BotPolicy::Allow => {}
+                BotPolicy::Tag => {
+                    event = event.prop("user_agent_class", user_agent_class.as_str().to_string());
+                }
+                BotPolicy::Drop => {
+                    debug!(
+                        "dropping '{}' event from '{user_agent_class}' user agent: {user_agent:?}",
+                        event.name
+                    );
+                    return Ok(());
+                }
+            }
+        }
+
+        // generate headers (Plausible needs the full IP address to count unique visitors)
+        let real_client_ip: String =
+            resolve_true_client_ip_address(addr, headers, &self.ip_anonymizer);
+
+        // deduplicate pageviews
+        if event.name == PAGEVIEW_EVENT
+            && self.is_duplicate_pageview(&real_client_ip, &user_agent, &event.url)
+        {
+            debug!("dropping duplicate pageview of {:?}", event.url);
+            return Ok(());
+        }
+
+        // generate payload
+        let outgoing_payload: PlausibleEventBody = PlausibleEventBody {
+            domain: domain.to_string(),
+            name: event.name,
+            url: event.url,
+            referrer: event.referrer,
+            screen_width: event.screen_width,
+            props: event
+                .props
+                .into_iter()
+                .map(|(key, value)| (key, prop_value_to_json(value)))
+                .collect(),
+            revenue: event.revenue,
+        };
+
+        let headers: EventHeaders = EventHeaders::new(user_agent, real_client_ip);
+
+        info!(
+            "Queueing Plausible Analytics call with user_agent={:?}, client_ip={:?} and body={:?}",
+            headers.user_agent,
+            self.ip_anonymizer.anonymize(&headers.x_forwarded_for),
+            outgoing_payload
+        );
+        self.queue.enqueue(QueuedEvent {
+            headers,
+            body: outgoing_payload,
+        })
+    }
+
+    /// Records the pageview and returns whether the same client already viewed the same page within the dedup window.
+    #[instrument(skip_all)]
+    fn is_duplicate_pageview(&self, client_ip: &str, user_agent: &str, url: &str) -> bool {
+        let Some(window) = self.pageview_dedup_window else {
+            return false;
+        };
+
+        // compare by path + query, since the browser and server may disagree on scheme/host (e.g. "www.")
+        let page: String = Url::parse(url).map_or_else(
+            |_| url.to_string(),
+            |url| format!("{}?{}", url.path(), url.query().unwrap_or_default()),
+        );
+        let mut hasher: DefaultHasher = DefaultHasher::new();
+        (client_ip, user_agent, page).hash(&mut hasher);
+        let key: u64 = hasher.finish();
+
+        let now: Instant = Instant::now();
+        let mut recent_pageviews = self
+            .recent_pageviews
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        // the first view starts the window, so reloading more often than `window` does not keep extending it
+        let duplicate: bool = match recent_pageviews.viewed_at.entry(key) {
+            Entry::Occupied(mut entry) => {
+                let duplicate: bool = now.duration_since(*entry.get()) < window;
+                if !duplicate {
+                    entry.insert(now);
+                }
+                duplicate
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(now);
+                false
+            }
+        };
+
+        // forget expired pageviews at most once per window, rather than scanning every pageview on each request
+        if now.duration_since(recent_pageviews.pruned_at) >= window {
+            recent_pageviews
+                .viewed_at
+                .retain(|_, viewed_at| now.duration_since(*viewed_at) < window);
+            recent_pageviews.pruned_at = now;
+        }
+        duplicate
+    }
+}
+
+#[instrument(skip_all)]
+fn prop_value_to_json(prop_value: PropValue) -> Value {
+    match prop_value {
+        PropValue::String(s) => Value::from(s),
+        PropValue::Bool(b) => Value::from(b),
+        PropValue::U8(u) => Value::from(u),
+        PropValue::U16(u) => Value::from(u),
+        PropValue::U32(u) => Value::from(u),
+        PropValue::U64(u) => Value::from(u),
+        PropValue::U128(u) => {
+            u64::try_from(u).map_or_else(|_| Value::from(u.to_string()), Value::from)
+        }
+        PropValue::Usize(u) => Value::from(u),
+        PropValue::I8(i) => Value::from(i),
+        PropValue::I16(i) => Value::from(i),
+        PropValue::I32(i) => Value::from(i),
+        PropValue::I64(i) => Value::from(i),
+        PropValue::I128(i) => {
+            i64::try_from(i).map_or_else(|_| Value::from(i.to_string()), Value::from)
+        }
+        PropValue::Isize(i) => Value::from(i),
+        PropValue::F32(f) => Value::from(f),
+        PropValue::F64(f) => Value::from(f),
+    }
+}
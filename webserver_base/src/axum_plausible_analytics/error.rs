@@ -0,0 +1,85 @@
+use reqwest::StatusCode;
+use std::fmt::{Debug, Formatter};
+use std::{error, fmt, io};
+
+#[derive(Debug)]
+pub enum AnalyticsError {
+    /// The analytics queue is full, so the event was dropped.
+    QueueFull,
+
+    /// The analytics queue has been shut down, so the event was dropped.
+    QueueClosed,
+
+    /// A call to Plausible Analytics failed.
+    PlausibleError(plausible_rs::Error),
+
+    /// Writing to a local analytics sink failed.
+    IoError(io::Error),
+
+    /// A browser sent a malformed (or oversized) analytics payload.
+    InvalidPayload(String),
+
+    /// A browser sent an analytics payload for a page that is not on one of our hosts.
+    ForeignUrl(String),
+}
+
+impl AnalyticsError {
+    /// Whether sending the event again could succeed (network errors, server errors and rate limits).
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::PlausibleError(plausible_rs::Error::ReqwestError(_)) => true,
+            Self::PlausibleError(plausible_rs::Error::RequestFailed { status_code, .. }) => {
+                status_code.is_server_error() || *status_code == StatusCode::TOO_MANY_REQUESTS
+            }
+            Self::PlausibleError(plausible_rs::Error::SerdeError(_))
+            | Self::QueueFull
+            | Self::QueueClosed
+            | Self::IoError(_)
+            | Self::InvalidPayload(_)
+            | Self::ForeignUrl(_) => false,
+        }
+    }
+
+    /// HTTP status code to respond to the browser with.
+    ///
+    /// Dropped events are accepted, so that the beacon does not retry them.
+    #[must_use]
+    pub const fn status_code(&self) -> StatusCode {
+        match self {
+            Self::InvalidPayload(_) => StatusCode::BAD_REQUEST,
+            Self::ForeignUrl(_) => StatusCode::FORBIDDEN,
+            Self::QueueFull | Self::QueueClosed => StatusCode::ACCEPTED,
+            Self::PlausibleError(_) | Self::IoError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl error::Error for AnalyticsError {}
+
+impl fmt::Display for AnalyticsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::QueueFull => write!(f, "analytics queue is full"),
+            Self::QueueClosed => write!(f, "analytics queue is closed"),
+            Self::PlausibleError(plausible_error) => std::fmt::Display::fmt(&plausible_error, f),
+            Self::IoError(io_error) => std::fmt::Display::fmt(&io_error, f),
+            Self::InvalidPayload(reason) => write!(f, "invalid analytics payload: {reason}"),
+            Self::ForeignUrl(host) => {
+                write!(f, "analytics payload is for a foreign host: '{host}'")
+            }
+        }
+    }
+}
+
+impl From<plausible_rs::Error> for AnalyticsError {
+    fn from(plausible_error: plausible_rs::Error) -> Self {
+        Self::PlausibleError(plausible_error)
+    }
+}
+
+impl From<io::Error> for AnalyticsError {
+    fn from(io_error: io::Error) -> Self {
+        Self::IoError(io_error)
+    }
+}
@@ -0,0 +1,18 @@
+mod analytics_event;
+mod analytics_queue;
+mod analytics_sink;
+#[expect(clippy::module_inception)]
+mod axum_plausible_analytics;
+mod error;
+mod pageview_middleware;
+mod payload_validator;
+mod request_payload;
+
+pub use analytics_event::*;
+pub use analytics_queue::{AnalyticsQueueConfig, AnalyticsStats};
+pub use analytics_sink::*;
+pub use axum_plausible_analytics::*;
+pub use error::*;
+pub use pageview_middleware::*;
+pub use payload_validator::*;
+pub use request_payload::*;
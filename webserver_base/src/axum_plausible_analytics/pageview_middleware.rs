@@ -0,0 +1,139 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{
+        HeaderMap, Method,
+        header::{CONTENT_TYPE, REFERER},
+    },
+    middleware::Next,
+    response::Response,
+};
+use tracing::{instrument, warn};
+
+use super::{AnalyticsEvent, AxumPlausibleAnalyticsHandler};
+use crate::base_settings::BaseSettings;
+use crate::server::UNIX_SOCKET_PEER_ADDR;
+
+/// Middleware that records a pageview for every successful HTML response, so visitors without JavaScript are counted.
+///
+/// Pair it with `AxumPlausibleAnalyticsHandler::dedup_pageviews` when the `scitylana.ts` beacon is also in use.
+///
+/// Clients are identified by their peer address (`ConnectInfo<SocketAddr>`) and forwarding headers; if the router is
+/// served without `ConnectInfo`, by the forwarding headers alone.
+///
+/// ```ignore
+/// router.layer(axum::middleware::from_fn_with_state(
+///     Arc::new(PageviewMiddleware::new(analytics, settings).exclude("/admin")),
+///     PageviewMiddleware::middleware,
+/// ))
+/// ```
+pub struct PageviewMiddleware {
+    analytics: Arc<AxumPlausibleAnalyticsHandler>,
+    settings: BaseSettings,
+
+    include_paths: Vec<String>,
+    exclude_paths: Vec<String>,
+}
+
+impl PageviewMiddleware {
+    /// Tracks every path except `/api` and `/static`.
+    #[must_use]
+    pub fn new(analytics: Arc<AxumPlausibleAnalyticsHandler>, settings: BaseSettings) -> Self {
+        Self {
+            analytics,
+            settings,
+            include_paths: Vec::new(),
+            exclude_paths: vec![String::from("/api"), String::from("/static")],
+        }
+    }
+
+    /// Only tracks paths starting with one of the included prefixes (all paths are tracked if none are included).
+    #[must_use]
+    pub fn include(mut self, path_prefix: &str) -> Self {
+        self.include_paths.push(path_prefix.to_string());
+        self
+    }
+
+    /// Never tracks paths starting with this prefix (takes precedence over `include`).
+    #[must_use]
+    pub fn exclude(mut self, path_prefix: &str) -> Self {
+        self.exclude_paths.push(path_prefix.to_string());
+        self
+    }
+
+    /// Prefixes match whole path segments: `/api` matches `/api` and `/api/v1`, but not `/apiary`.
+    #[must_use]
+    pub fn is_tracked_path(&self, path: &str) -> bool {
+        let included: bool = self.include_paths.is_empty()
+            || self
+                .include_paths
+                .iter()
+                .any(|prefix| has_path_prefix(path, prefix));
+        let excluded: bool = self
+            .exclude_paths
+            .iter()
+            .any(|prefix| has_path_prefix(path, prefix));
+
+        included && !excluded
+    }
+
+    #[instrument(skip_all)]
+    pub async fn middleware(
+        State(pageviews): State<Arc<Self>>,
+        connect_info: Option<ConnectInfo<SocketAddr>>,
+        req: Request,
+        next: Next,
+    ) -> Response {
+        if req.method() != Method::GET || !pageviews.is_tracked_path(req.uri().path()) {
+            return next.run(req).await;
+        }
+
+        let headers: HeaderMap = req.headers().clone();
+        let path_and_query: String = req
+            .uri()
+            .path_and_query()
+            .map_or_else(|| req.uri().path().to_string(), ToString::to_string);
+
+        let response: Response = next.run(req).await;
+
+        let is_html: bool = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(|content_type| content_type.starts_with("text/html"));
+        if !response.status().is_success() || !is_html {
+            return response;
+        }
+
+        let url: String = format!(
+            "{}{path_and_query}",
+            pageviews.settings.home_url.trim_end_matches('/')
+        );
+        let mut event: AnalyticsEvent = AnalyticsEvent::pageview(&url);
+        if let Some(referrer) = headers
+            .get(REFERER)
+            .and_then(|referrer| referrer.to_str().ok())
+        {
+            event = event.referrer(referrer.to_string());
+        }
+
+        // the placeholder is only used if the request has no forwarding headers either
+        let addr: SocketAddr = connect_info.map_or(UNIX_SOCKET_PEER_ADDR, |ConnectInfo(addr)| addr);
+        if let Err(e) = pageviews
+            .analytics
+            .track(&headers, &pageviews.settings, addr, event)
+        {
+            warn!("failed to track server-side pageview of '{path_and_query}': {e}");
+        }
+
+        response
+    }
+}
+
+fn has_path_prefix(path: &str, prefix: &str) -> bool {
+    let prefix: &str = prefix.trim_end_matches('/');
+    path.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
@@ -0,0 +1,168 @@
+use reqwest::Url;
+use tracing::instrument;
+
+use super::{AnalyticsError, RequestPayload};
+use crate::base_settings::{BaseSettings, Environment};
+
+/// Request body limit for the analytics route; well above what the default `PayloadValidator` limits allow once
+/// form-encoded, so that oversized fields are rejected with its descriptive errors rather than the server-wide body
+/// limit's bare `413 Payload Too Large`.
+pub const ANALYTICS_BODY_LIMIT: usize = 64 * 1024;
+
+/// Validates and sanitises the analytics payloads sent by browsers before they are forwarded.
+///
+/// - rejects pages that are not on `BaseSettings::home_url`'s host (or one of `allowed_hosts`), so third parties
+///   cannot spam our stats
+/// - rejects fields that are longer than their limit
+/// - strips every query parameter that is not on the allowlist (UTM parameters are kept) and the fragment from the page
+///   URL, and the query/fragment from the referrer
+#[derive(Debug, Clone)]
+pub struct PayloadValidator {
+    pub max_url_length: usize,
+    pub max_referrer_length: usize,
+    pub max_user_agent_length: usize,
+    pub max_name_length: usize,
+    pub max_props_length: usize,
+
+    pub allowed_query_params: Vec<String>,
+    pub allowed_hosts: Vec<String>,
+}
+
+impl Default for PayloadValidator {
+    fn default() -> Self {
+        Self {
+            max_url_length: 2048,
+            max_referrer_length: 2048,
+            max_user_agent_length: 512,
+            max_name_length: 120,
+            max_props_length: 2048,
+
+            // query parameters that Plausible uses for its Sources reports
+            allowed_query_params: [
+                "utm_source",
+                "utm_medium",
+                "utm_campaign",
+                "utm_term",
+                "utm_content",
+                "ref",
+                "source",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+            allowed_hosts: Vec::new(),
+        }
+    }
+}
+
+impl PayloadValidator {
+    /// # Errors
+    ///
+    /// Will return `AnalyticsError::InvalidPayload` if a field is too long or the page URL is malformed, and
+    /// `AnalyticsError::ForeignUrl` if the page URL is not on one of our hosts.
+    #[instrument(skip_all)]
+    pub fn validate(
+        &self,
+        mut payload: RequestPayload,
+        settings: &BaseSettings,
+    ) -> Result<RequestPayload, AnalyticsError> {
+        // lengths
+        check_length("url", &payload.url, self.max_url_length)?;
+        check_length("referrer", &payload.referrer, self.max_referrer_length)?;
+        check_length(
+            "user_agent",
+            &payload.user_agent,
+            self.max_user_agent_length,
+        )?;
+        if let Some(name) = &payload.name {
+            check_length("name", name, self.max_name_length)?;
+        }
+        if let Some(props) = &payload.props {
+            check_length("props", props, self.max_props_length)?;
+        }
+
+        // page URL must be one of ours
+        let mut url: Url = Url::parse(&payload.url).map_err(|e| {
+            AnalyticsError::InvalidPayload(format!("`url` is not a valid URL: {e}"))
+        })?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(AnalyticsError::InvalidPayload(format!(
+                "`url` has an unsupported scheme: '{}'",
+                url.scheme()
+            )));
+        }
+        let host: &str = url.host_str().unwrap_or_default();
+        if !self.is_allowed_host(host, settings) {
+            return Err(AnalyticsError::ForeignUrl(host.to_string()));
+        }
+
+        // strip everything but allowlisted query parameters
+        let kept_query_params: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(key, _)| {
+                self.allowed_query_params
+                    .iter()
+                    .any(|allowed| allowed == key)
+            })
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+        url.set_query(None);
+        if !kept_query_params.is_empty() {
+            url.query_pairs_mut().extend_pairs(kept_query_params);
+        }
+        url.set_fragment(None);
+        payload.url = url.to_string();
+
+        // only keep the referrer's origin and path
+        payload.referrer = Url::parse(&payload.referrer).map_or_else(
+            |_| String::new(),
+            |mut referrer| {
+                referrer.set_query(None);
+                referrer.set_fragment(None);
+                referrer.to_string()
+            },
+        );
+
+        Ok(payload)
+    }
+
+    fn is_allowed_host(&self, host: &str, settings: &BaseSettings) -> bool {
+        let host: &str = strip_www(host);
+
+        let home_host: Option<String> = Url::parse(&settings.home_url).ok().and_then(|home_url| {
+            home_url
+                .host_str()
+                .map(|home_host| strip_www(home_host).to_string())
+        });
+        if home_host.as_deref() == Some(host) {
+            return true;
+        }
+
+        if self
+            .allowed_hosts
+            .iter()
+            .any(|allowed_host| strip_www(allowed_host) == host)
+        {
+            return true;
+        }
+
+        // local development servers are never on `home_url`'s host
+        matches!(
+            settings.environment,
+            Environment::Development | Environment::Test
+        ) && matches!(host, "localhost" | "127.0.0.1" | "[::1]")
+    }
+}
+
+fn check_length(field: &str, value: &str, max_length: usize) -> Result<(), AnalyticsError> {
+    if value.chars().count() > max_length {
+        return Err(AnalyticsError::InvalidPayload(format!(
+            "`{field}` is longer than {max_length} characters"
+        )));
+    }
+    Ok(())
+}
+
+fn strip_www(host: &str) -> &str {
+    host.strip_prefix("www.").unwrap_or(host)
+}
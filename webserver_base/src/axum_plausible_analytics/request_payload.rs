@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+use plausible_rs::PropValue;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{instrument, warn};
+
+use super::{AnalyticsEvent, Revenue};
+
+/// Form payload sent by the browser (see `scitylana.ts`).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RequestPayload {
+    pub user_agent: String,
+    pub url: String,
+    pub referrer: String,
+    pub screen_width: usize,
+
+    /// Name of the event (defaults to a pageview).
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Custom properties as a JSON object of scalar values (form payloads cannot nest, so this is a JSON string).
+    #[serde(default)]
+    pub props: Option<String>,
+
+    #[serde(default)]
+    pub revenue_currency: Option<String>,
+    #[serde(default)]
+    pub revenue_amount: Option<String>,
+}
+
+impl RequestPayload {
+    /// Converts the browser's payload into an `AnalyticsEvent`.
+    ///
+    /// Custom properties that are not valid JSON, or are not scalar values, are dropped (and logged).
+    #[must_use]
+    #[instrument(skip_all)]
+    pub fn to_analytics_event(&self) -> AnalyticsEvent {
+        let mut event: AnalyticsEvent = match self.name.as_deref().map(str::trim) {
+            Some(name) if !name.is_empty() => AnalyticsEvent::new(name, &self.url),
+            _ => AnalyticsEvent::pageview(&self.url),
+        }
+        .referrer(self.referrer.clone())
+        .screen_width(self.screen_width);
+
+        if let Some(props) = self.props.as_deref().filter(|props| !props.is_empty()) {
+            match serde_json::from_str::<HashMap<String, Value>>(props) {
+                Ok(props) => {
+                    for (key, value) in props {
+                        if let Some(prop_value) = json_to_prop_value(value) {
+                            event = event.prop(&key, prop_value);
+                        } else {
+                            warn!("dropping non-scalar analytics prop: '{key}'");
+                        }
+                    }
+                }
+                Err(e) => warn!("dropping unparseable analytics props: {e}"),
+            }
+        }
+
+        match (&self.revenue_currency, &self.revenue_amount) {
+            (Some(currency), Some(amount)) if !currency.is_empty() && !amount.is_empty() => {
+                event = event.revenue(Revenue::new(currency.clone(), amount.clone()));
+            }
+            (None, None) => {}
+            _ => warn!("dropping analytics revenue: both currency and amount are required"),
+        }
+
+        event
+    }
+}
+
+#[instrument(skip_all)]
+fn json_to_prop_value(value: Value) -> Option<PropValue> {
+    match value {
+        Value::String(s) => Some(PropValue::from(s)),
+        Value::Bool(b) => Some(PropValue::from(b)),
+        Value::Number(n) => n
+            .as_i64()
+            .map(PropValue::from)
+            .or_else(|| n.as_u64().map(PropValue::from))
+            .or_else(|| n.as_f64().map(PropValue::from)),
+        Value::Null | Value::Array(_) | Value::Object(_) => None,
+    }
+}
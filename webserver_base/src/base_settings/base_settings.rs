@@ -1,84 +1,76 @@
-use std::env;
+use super::{Environment, EnvironmentProfile, LogFormat, Secret, SettingsLoader};
+use crate::ip::{IpAnonymization, IpNetwork};
+use crate::server::{ListenerConfig, TlsConfig};
 
-use super::Environment;
-
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct BaseSettings {
     pub host: String,
     pub port: u16,
+    /// Where the server accepts connections; `host:port` unless `listen` is set.
+    pub listeners: Vec<ListenerConfig>,
+    /// Certificate for `https://` listeners.
+    pub tls: Option<TlsConfig>,
     pub environment: Environment,
+    /// The environment's log format unless `log_format` is set (e.g. `json` in production).
+    pub log_format: LogFormat,
 
     pub project_name: String,
     pub project_description: String,
     pub project_keywords: String,
     pub home_url: String,
 
-    pub analytics_domain: String,
-    pub sentry_dsn: String,
+    /// Analytics are disabled when unset.
+    pub analytics_domain: Option<String>,
+    pub non_production_analytics_domain: Option<String>,
+    pub analytics_enabled: bool,
+    /// Error monitoring is disabled when unset.
+    pub sentry_dsn: Option<Secret>,
+
+    pub ip_anonymization: IpAnonymization,
+
+    /// A separate, internal listener for `/metrics` (e.g. `127.0.0.1:9090`); otherwise `/metrics` is served on the
+    /// main listeners to `metrics_allowlist` only.
+    pub metrics_listen: Option<ListenerConfig>,
+    /// Networks allowed to scrape `/metrics` on the main listeners; when empty (the default), `/metrics` is not served
+    /// on them at all.
+    pub metrics_allowlist: Vec<IpNetwork>,
 }
 
 impl Default for BaseSettings {
+    /// Loads the settings from environment variables only (see `SettingsLoader` for config files, prefixes and
+    /// command-line arguments).
+    ///
     /// # Panics
     ///
-    /// Will panic if env var `PORT` is not a u16.
+    /// Will panic if a required environment variable is not set, or if any setting is invalid.
     fn default() -> Self {
-        // env
-        let environment = env::var("ENVIRONMENT").map_or(Environment::Development, |s| {
-            Environment::try_from(s.clone()).unwrap_or_else(|_| {
-                panic!("failed to parse `ENVIRONMENT` environment variable: {s}")
-            })
-        });
-
-        // host:port
-        let host = env::var("HOST").unwrap_or_else(|_| {
-            if environment == Environment::Development {
-                "127.0.0.1".to_string()
-            } else {
-                "0.0.0.0".to_string()
-            }
-        });
-        let port = env::var("PORT").map_or(8080, |s| {
-            s.parse::<u16>()
-                .expect("failed to parse `PORT` environment variable")
-        });
-
-        // project details
-        let Ok(project_name) = env::var("PROJECT_NAME") else {
-            panic!("environment variable `PROJECT_NAME` is not set");
-        };
-        let Ok(project_description) = env::var("PROJECT_DESCRIPTION") else {
-            panic!("environment variable `PROJECT_DESCRIPTION` is not set");
-        };
-        let Ok(project_keywords) = env::var("PROJECT_KEYWORDS") else {
-            panic!("environment variable `PROJECT_KEYWORDS` is not set");
-        };
-        let Ok(home_url) = env::var("HOME_URL") else {
-            panic!("environment variable `HOME_URL` is not set");
-        };
-
-        // analytics domain
-        let Ok(analytics_domain) = env::var("ANALYTICS_DOMAIN") else {
-            panic!("environment variable `ANALYTICS_DOMAIN` is not set");
-        };
-
-        // sentry DSN
-        let Ok(sentry_dsn) = env::var("SENTRY_DSN") else {
-            panic!("environment variable `SENTRY_DSN` is not set");
-        };
+        SettingsLoader::new()
+            .load()
+            .unwrap_or_else(|e| panic!("failed to load settings:\n{e}"))
+    }
+}
 
-        // all settings
-        Self {
-            host,
-            port,
-            environment,
+impl BaseSettings {
+    /// The defaults implied by `environment`.
+    #[must_use]
+    pub const fn profile(&self) -> EnvironmentProfile {
+        self.environment.profile()
+    }
 
-            project_name,
-            project_description,
-            project_keywords,
-            home_url,
+    /// The Plausible Analytics domain that events should be reported to in the current environment.
+    ///
+    /// Returns `None` when analytics are disabled, which is always the case outside of production unless that
+    /// environment has its own domain configured.
+    #[must_use]
+    pub fn reported_analytics_domain(&self) -> Option<&str> {
+        if !self.analytics_enabled {
+            return None;
+        }
 
-            analytics_domain,
-            sentry_dsn,
+        if self.environment == Environment::Production {
+            self.analytics_domain.as_deref()
+        } else {
+            self.non_production_analytics_domain.as_deref()
         }
     }
 }
@@ -1,9 +1,17 @@
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
-#[derive(PartialEq, Eq, Clone, Default)]
+use serde::{Deserialize, Serialize};
+
+use super::EnvironmentProfile;
+
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub enum Environment {
     #[default]
     Development,
+    Test,
+    Staging,
     Production,
 }
 
@@ -12,30 +20,56 @@ impl Environment {
     pub const fn as_str(&self) -> &'static str {
         match self {
             Self::Development => "development",
+            Self::Test => "test",
+            Self::Staging => "staging",
             Self::Production => "production",
         }
     }
+
+    /// The defaults that every subsystem uses in this environment.
+    #[must_use]
+    pub const fn profile(&self) -> EnvironmentProfile {
+        match self {
+            Self::Development => EnvironmentProfile::DEVELOPMENT,
+            Self::Test => EnvironmentProfile::TEST,
+            Self::Staging => EnvironmentProfile::STAGING,
+            Self::Production => EnvironmentProfile::PRODUCTION,
+        }
+    }
 }
 
 impl TryFrom<String> for Environment {
     type Error = String;
 
     fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl FromStr for Environment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "development" => Ok(Self::Development),
+            "test" => Ok(Self::Test),
+            "staging" => Ok(Self::Staging),
             "production" => Ok(Self::Production),
             other => Err(format!(
-                "{other} is not a supported environment. Use either `development` or `production`."
+                "{other} is not a supported environment. Use either `development`, `test`, `staging` or `production`."
             )),
         }
     }
 }
 
+impl From<Environment> for String {
+    fn from(environment: Environment) -> Self {
+        environment.as_str().to_string()
+    }
+}
+
 impl Display for Environment {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Development => write!(f, "development"),
-            Self::Production => write!(f, "production"),
-        }
+        write!(f, "{}", self.as_str())
     }
 }
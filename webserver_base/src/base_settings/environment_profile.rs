@@ -0,0 +1,89 @@
+use std::str::FromStr;
+
+use crate::cache_buster::CachePolicy;
+
+/// How log lines are formatted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Multi-line and colourful, for reading in a terminal.
+    Pretty,
+
+    /// One line per event, for log aggregators.
+    Compact,
+
+    /// One JSON object per event, including the fields of its spans (e.g. the request ID), for structured log
+    /// aggregators.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pretty" => Ok(Self::Pretty),
+            "compact" => Ok(Self::Compact),
+            "json" => Ok(Self::Json),
+            other => Err(format!(
+                "{other} is not a supported log format. Use either `pretty`, `compact` or `json`."
+            )),
+        }
+    }
+}
+
+/// The defaults that an `Environment` implies, so subsystems consult one place instead of branching on the
+/// environment themselves.
+///
+/// Explicit settings (e.g. `HOST` or `ANALYTICS_ENABLED`) still override these defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnvironmentProfile {
+    /// Address the server binds to when `host` is not set.
+    pub bind_host: &'static str,
+
+    /// Log format when `log_format` is not set.
+    pub log_format: LogFormat,
+
+    /// Whether analytics are enabled when `analytics_enabled` is not set.
+    pub analytics_enabled: bool,
+
+    /// Cache policy for (cache-busted) static assets.
+    pub static_cache_policy: CachePolicy,
+
+    /// Whether error responses include the underlying error message, rather than only the status.
+    pub verbose_errors: bool,
+}
+
+impl EnvironmentProfile {
+    pub const DEVELOPMENT: Self = Self {
+        bind_host: "127.0.0.1",
+        log_format: LogFormat::Pretty,
+        analytics_enabled: true,
+        static_cache_policy: CachePolicy::NoCache,
+        verbose_errors: true,
+    };
+
+    pub const TEST: Self = Self {
+        bind_host: "127.0.0.1",
+        log_format: LogFormat::Compact,
+        analytics_enabled: false,
+        static_cache_policy: CachePolicy::NoCache,
+        verbose_errors: true,
+    };
+
+    pub const STAGING: Self = Self {
+        bind_host: "0.0.0.0",
+        log_format: LogFormat::Compact,
+        analytics_enabled: true,
+        static_cache_policy: CachePolicy::Forever,
+        verbose_errors: true,
+    };
+
+    pub const PRODUCTION: Self = Self {
+        bind_host: "0.0.0.0",
+        // structured, for log aggregators
+        log_format: LogFormat::Json,
+        analytics_enabled: true,
+        static_cache_policy: CachePolicy::Forever,
+        verbose_errors: false,
+    };
+}
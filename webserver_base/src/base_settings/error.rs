@@ -0,0 +1,72 @@
+use std::fmt::{Debug, Formatter};
+use std::path::PathBuf;
+use std::{error, fmt, io};
+
+#[derive(Debug)]
+pub enum SettingsError {
+    /// The config file, or a file named by a `*_FILE` environment variable, could not be read.
+    FileIOError(PathBuf, io::Error),
+
+    /// The config file is not valid TOML/YAML, or does not have a `.toml`, `.yaml` or `.yml` extension.
+    FileFormatError(PathBuf, String),
+
+    /// A required setting is not set by any layer.
+    Missing { key: String, env_var: String },
+
+    /// A setting has a value that cannot be parsed.
+    Invalid {
+        key: String,
+        value: String,
+        reason: String,
+    },
+
+    /// A config file key or command-line argument is not a known setting.
+    Unknown(String),
+
+    /// The `extra` section cannot be deserialised into the project's settings type.
+    InvalidExtra(String),
+
+    /// Every error found while loading the settings.
+    Multiple(Vec<SettingsError>),
+}
+
+impl error::Error for SettingsError {}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FileIOError(path, io_error) => {
+                write!(f, "failed to read '{}': {io_error}", path.display())
+            }
+            Self::FileFormatError(path, reason) => {
+                write!(
+                    f,
+                    "failed to parse config file '{}': {reason}",
+                    path.display()
+                )
+            }
+            Self::Missing { key, env_var } => write!(
+                f,
+                "setting `{key}` is not set (config file key `{key}`, environment variable `{env_var}` or argument `--{}`)",
+                key.replace('_', "-")
+            ),
+            Self::Invalid { key, value, reason } => {
+                write!(
+                    f,
+                    "setting `{key}` has an invalid value '{value}': {reason}"
+                )
+            }
+            Self::Unknown(key) => write!(f, "unknown setting `{key}`"),
+            Self::InvalidExtra(reason) => write!(f, "invalid `extra` settings: {reason}"),
+            Self::Multiple(errors) => {
+                for (i, settings_error) in errors.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "- {settings_error}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use serde::de::{Deserializer, IntoDeserializer, Visitor};
+use serde_json::Value;
+
+/// A value of the `extra` settings section, deserialised into whatever type the project's settings declare.
+///
+/// Config files are typed, but environment variables and command-line arguments are text: those are only parsed (as
+/// JSON) when the target is not a string, so e.g. `EXTRA_ZIP=01234` stays `"01234"` for a `String` field while
+/// `EXTRA_PAGE_SIZE=20` still fills a `u32`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ExtraValue {
+    /// From a config file.
+    Typed(Value),
+
+    /// From an environment variable or command-line argument.
+    Text(String),
+}
+
+impl ExtraValue {
+    fn parsed(self) -> Result<Value, serde_json::Error> {
+        match self {
+            Self::Typed(value) => Ok(value),
+            Self::Text(text) => serde_json::from_str(&text),
+        }
+    }
+}
+
+/// Deserialises text as a string.
+macro_rules! deserialize_as_text {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                match self {
+                    Self::Typed(value) => value.$method(visitor),
+                    Self::Text(text) => visitor.visit_string(text),
+                }
+            }
+        )*
+    };
+}
+
+/// Deserialises text by parsing it as JSON first.
+macro_rules! deserialize_parsed {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.parsed()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for ExtraValue {
+    type Error = serde_json::Error;
+
+    deserialize_as_text! {
+        deserialize_any deserialize_str deserialize_string deserialize_char deserialize_bytes deserialize_byte_buf
+        deserialize_identifier
+    }
+
+    deserialize_parsed! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128 deserialize_f32
+        deserialize_f64 deserialize_unit deserialize_seq deserialize_map deserialize_ignored_any
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self {
+            Self::Typed(value) => value.deserialize_option(visitor),
+            // `EXTRA_NAME=null` is the string "null"; leave the variable unset for `None`
+            text @ Self::Text(_) => visitor.visit_some(text),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.parsed()?.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_tuple<V>(self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.parsed()?.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.parsed()?.deserialize_tuple_struct(name, len, visitor)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.parsed()?.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        // unit variants by name (e.g. `EXTRA_THEME=dark`)
+        match self {
+            Self::Typed(value) => value.deserialize_enum(name, variants, visitor),
+            Self::Text(text) => Value::String(text).deserialize_enum(name, variants, visitor),
+        }
+    }
+}
+
+impl IntoDeserializer<'_, serde_json::Error> for ExtraValue {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
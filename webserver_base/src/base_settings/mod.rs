@@ -1,6 +1,15 @@
 #[expect(clippy::module_inception)]
 mod base_settings;
 mod environment;
+mod environment_profile;
+mod error;
+mod extra_value;
+mod secret;
+mod settings_loader;
 
 pub use base_settings::*;
 pub use environment::*;
+pub use environment_profile::*;
+pub use error::*;
+pub use secret::*;
+pub use settings_loader::*;
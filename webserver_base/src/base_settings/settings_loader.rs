@@ -0,0 +1,568 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::de::value::MapDeserializer;
+use serde_json::{Map, Value};
+use tracing::{debug, instrument};
+
+use super::extra_value::ExtraValue;
+use super::{BaseSettings, Environment, LogFormat, Secret, SettingsError};
+use crate::ip::{IpAnonymization, IpNetwork};
+use crate::server::{ListenerConfig, TlsConfig};
+
+/// Settings that `BaseSettings` knows about, as they are named in config files (command-line arguments use
+/// `kebab-case`, and environment variables `UPPER_CASE` plus the prefix).
+const KNOWN_KEYS: [&str; 17] = [
+    "host",
+    "port",
+    "listen",
+    "tls_cert_path",
+    "tls_key_path",
+    "environment",
+    "log_format",
+    "project_name",
+    "project_description",
+    "project_keywords",
+    "home_url",
+    "analytics_domain",
+    "analytics_enabled",
+    "sentry_dsn",
+    "ip_anonymization",
+    "metrics_listen",
+    "metrics_allowlist",
+];
+
+/// Per-environment analytics domains (e.g. `analytics_domain_development`) are also known keys.
+const NON_PRODUCTION_ANALYTICS_DOMAIN_KEY_PREFIX: &str = "analytics_domain_";
+
+/// Project-specific settings live in this section of the config file, and under this prefix in environment variables
+/// (e.g. `MYSITE_EXTRA_NEWSLETTER_URL`, only read with an `env_prefix`) and command-line arguments (e.g.
+/// `--extra.newsletter_url=...`).
+const EXTRA_KEY: &str = "extra";
+
+/// Environment variables with this suffix hold the path of a file that contains the setting's value (e.g.
+/// `SENTRY_DSN_FILE`).
+const FILE_ENV_VAR_SUFFIX: &str = "_file";
+
+/// Loads `BaseSettings` from up to three layers, each overriding the one before it:
+///
+/// 1. a TOML or YAML config file
+/// 2. environment variables (optionally prefixed, e.g. `MYSITE_PORT`), or files named by `*_FILE` environment
+///    variables (e.g. `MYSITE_SENTRY_DSN_FILE=/run/secrets/sentry_dsn`)
+/// 3. command-line arguments (`--port=8080` or `--port 8080`)
+///
+/// Every missing or invalid setting is reported at once.
+#[derive(Debug, Clone, Default)]
+pub struct SettingsLoader {
+    file: Option<PathBuf>,
+    env_prefix: String,
+    env_vars: Option<BTreeMap<String, String>>,
+    args: Vec<String>,
+}
+
+impl SettingsLoader {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads settings from a config file; its format is chosen by extension (`.toml`, `.yaml` or `.yml`).
+    #[must_use]
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.file = Some(path.into());
+        self
+    }
+
+    /// Only reads environment variables that start with `prefix` (e.g. `MYSITE_`); unprefixed by default.
+    ///
+    /// Without a prefix, `extra` settings are not read from environment variables, since unrelated `EXTRA_*` variables
+    /// would end up in them.
+    #[must_use]
+    pub fn env_prefix(mut self, prefix: &str) -> Self {
+        self.env_prefix = prefix.to_string();
+        self
+    }
+
+    /// Reads environment variables from `vars` instead of the process environment (e.g. in tests).
+    #[must_use]
+    pub fn env_vars<I>(mut self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        self.env_vars = Some(vars.into_iter().collect());
+        self
+    }
+
+    /// Reads settings from command-line arguments, excluding the program name (e.g. `env::args().skip(1)`).
+    #[must_use]
+    pub fn args<I>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        self.args = args.into_iter().collect();
+        self
+    }
+
+    /// # Errors
+    ///
+    /// Will return `SettingsError::Multiple` with every missing or invalid setting.
+    #[instrument(skip_all)]
+    pub fn load(self) -> Result<BaseSettings, SettingsError> {
+        let mut errors: Vec<SettingsError> = Vec::new();
+        let layers: Layers = self.read_layers(&mut errors);
+        let base_settings: BaseSettings = layers.base_settings(&self.env_prefix, &mut errors);
+
+        if errors.is_empty() {
+            Ok(base_settings)
+        } else {
+            Err(SettingsError::Multiple(errors))
+        }
+    }
+
+    /// Like `load()`, and also deserialises the `extra` section into the project's own settings type.
+    ///
+    /// # Errors
+    ///
+    /// Will return `SettingsError::Multiple` with every missing or invalid setting (including the `extra` section).
+    #[instrument(skip_all)]
+    pub fn load_with_extra<E>(self) -> Result<(BaseSettings, E), SettingsError>
+    where
+        E: DeserializeOwned,
+    {
+        let mut errors: Vec<SettingsError> = Vec::new();
+        let layers: Layers = self.read_layers(&mut errors);
+        let base_settings: BaseSettings = layers.base_settings(&self.env_prefix, &mut errors);
+        let extra: Option<E> = E::deserialize(MapDeserializer::new(layers.extra.into_iter()))
+            .map_err(|e| errors.push(SettingsError::InvalidExtra(e.to_string())))
+            .ok();
+
+        match extra {
+            Some(extra) if errors.is_empty() => Ok((base_settings, extra)),
+            _ => Err(SettingsError::Multiple(errors)),
+        }
+    }
+
+    fn read_layers(&self, errors: &mut Vec<SettingsError>) -> Layers {
+        let mut layers: Layers = Layers::default();
+        if let Some(file) = &self.file {
+            layers.read_file(file, errors);
+        }
+        let env_vars: BTreeMap<String, String> = self
+            .env_vars
+            .clone()
+            .unwrap_or_else(|| env::vars().collect());
+        layers.read_env(&env_vars, &self.env_prefix, errors);
+        layers.read_args(&self.args, errors);
+        layers
+    }
+}
+
+#[derive(Debug, Default)]
+struct Layers {
+    values: BTreeMap<String, String>,
+    extra: BTreeMap<String, ExtraValue>,
+}
+
+impl Layers {
+    fn read_file(&mut self, path: &Path, errors: &mut Vec<SettingsError>) {
+        let contents: String = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                errors.push(SettingsError::FileIOError(path.to_path_buf(), e));
+                return;
+            }
+        };
+
+        let extension: String = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let parsed: Result<Value, String> = match extension.as_str() {
+            "toml" => toml::from_str(&contents).map_err(|e| e.to_string()),
+            "yaml" | "yml" => serde_yaml_ng::from_str(&contents).map_err(|e| e.to_string()),
+            _ => Err(String::from(
+                "unsupported format (expected a `.toml`, `.yaml` or `.yml` file)",
+            )),
+        };
+        let table: Map<String, Value> = match parsed {
+            Ok(Value::Object(table)) => table,
+            Ok(Value::Null) => Map::new(),
+            Ok(_) => {
+                errors.push(SettingsError::FileFormatError(
+                    path.to_path_buf(),
+                    String::from("expected a table of settings"),
+                ));
+                return;
+            }
+            Err(reason) => {
+                errors.push(SettingsError::FileFormatError(path.to_path_buf(), reason));
+                return;
+            }
+        };
+
+        for (key, value) in table {
+            if key == EXTRA_KEY {
+                match value {
+                    Value::Object(extra) => self.extra.extend(
+                        extra
+                            .into_iter()
+                            .map(|(key, value)| (key, ExtraValue::Typed(value))),
+                    ),
+                    value => errors.push(SettingsError::InvalidExtra(format!(
+                        "expected a table, found '{value}'"
+                    ))),
+                }
+                continue;
+            }
+            if !is_known_key(&key) {
+                errors.push(SettingsError::Unknown(key));
+                continue;
+            }
+
+            match value {
+                Value::Null => {}
+                Value::String(value) => {
+                    self.values.insert(key, value);
+                }
+                value @ (Value::Bool(_) | Value::Number(_)) => {
+                    self.values.insert(key, value.to_string());
+                }
+                value => errors.push(SettingsError::Invalid {
+                    key,
+                    value: value.to_string(),
+                    reason: String::from("expected a string, number or boolean"),
+                }),
+            }
+        }
+    }
+
+    fn read_env(
+        &mut self,
+        env_vars: &BTreeMap<String, String>,
+        prefix: &str,
+        errors: &mut Vec<SettingsError>,
+    ) {
+        let extra_prefix: String = format!("{}_", EXTRA_KEY.to_uppercase());
+        let mut file_values: BTreeMap<String, String> = BTreeMap::new();
+        for (name, value) in env_vars {
+            let Some(name) = name.strip_prefix(prefix) else {
+                continue;
+            };
+
+            if let Some(extra_key) = name.strip_prefix(&extra_prefix) {
+                if prefix.is_empty() {
+                    debug!(
+                        "ignoring `{name}`: `extra` settings are only read from prefixed environment variables"
+                    );
+                } else {
+                    self.extra
+                        .insert(extra_key.to_lowercase(), ExtraValue::Text(value.clone()));
+                }
+                continue;
+            }
+
+            let key: String = name.to_lowercase();
+            if is_known_key(&key) {
+                self.values.insert(key, value.clone());
+            } else if let Some(key) = key
+                .strip_suffix(FILE_ENV_VAR_SUFFIX)
+                .filter(|key| is_known_key(key))
+            {
+                // e.g. `SENTRY_DSN_FILE=/run/secrets/sentry_dsn` (Docker/Kubernetes secrets)
+                match fs::read_to_string(value) {
+                    Ok(contents) => {
+                        file_values.insert(key.to_string(), contents.trim_end().to_string());
+                    }
+                    Err(e) => errors.push(SettingsError::FileIOError(PathBuf::from(value), e)),
+                }
+            }
+        }
+
+        for (key, value) in file_values {
+            if env_vars.contains_key(&format!("{prefix}{}", key.to_uppercase())) {
+                errors.push(SettingsError::Invalid {
+                    value: String::from("[REDACTED]"),
+                    reason: format!(
+                        "both `{prefix}{0}` and `{prefix}{0}{1}` are set",
+                        key.to_uppercase(),
+                        FILE_ENV_VAR_SUFFIX.to_uppercase()
+                    ),
+                    key,
+                });
+            } else {
+                self.values.insert(key, value);
+            }
+        }
+    }
+
+    fn read_args(&mut self, args: &[String], errors: &mut Vec<SettingsError>) {
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let Some(arg) = arg.strip_prefix("--") else {
+                errors.push(SettingsError::Unknown(arg.clone()));
+                continue;
+            };
+            let (name, value): (&str, String) = if let Some((name, value)) = arg.split_once('=') {
+                (name, value.to_string())
+            } else if let Some(value) = args.next() {
+                (arg, value.clone())
+            } else {
+                errors.push(SettingsError::Invalid {
+                    key: arg.to_string(),
+                    value: String::new(),
+                    reason: String::from("missing value"),
+                });
+                continue;
+            };
+
+            let key: String = name.replace('-', "_");
+            if let Some(extra_key) = key.strip_prefix(&format!("{EXTRA_KEY}.")) {
+                self.extra
+                    .insert(extra_key.to_string(), ExtraValue::Text(value));
+            } else if is_known_key(&key) {
+                self.values.insert(key, value);
+            } else {
+                errors.push(SettingsError::Unknown(name.to_string()));
+            }
+        }
+    }
+
+    fn base_settings(&self, env_prefix: &str, errors: &mut Vec<SettingsError>) -> BaseSettings {
+        let setting = Setting {
+            values: &self.values,
+            env_prefix,
+        };
+
+        // env
+        let environment: Environment =
+            setting.parsed("environment", Environment::default(), errors, str::parse);
+        let log_format: LogFormat = setting.parsed(
+            "log_format",
+            environment.profile().log_format,
+            errors,
+            str::parse,
+        );
+
+        // host:port
+        let host: String = setting
+            .optional("host")
+            .unwrap_or_else(|| environment.profile().bind_host.to_string());
+        let port: u16 = setting.parsed("port", 8080, errors, str::parse::<u16>);
+
+        // listeners, and the TLS certificate for `https://` listeners
+        let listeners: Vec<ListenerConfig> = setting.listeners(&host, port, errors);
+        let tls: Option<TlsConfig> = setting.tls(&listeners, errors);
+
+        // project details
+        let project_name: String = setting.required("project_name", errors);
+        let project_description: String = setting.required("project_description", errors);
+        let project_keywords: String = setting.required("project_keywords", errors);
+        let home_url: String = setting.required("home_url", errors);
+
+        // analytics domain (empty when e.g. `ANALYTICS_DOMAIN=` is set by a template, which disables analytics like unset)
+        let analytics_domain: Option<String> = setting
+            .optional("analytics_domain")
+            .filter(|analytics_domain| !analytics_domain.is_empty());
+        // e.g. `analytics_domain_development` (analytics are disabled outside of production when unset)
+        let non_production_analytics_domain: Option<String> =
+            if environment == Environment::Production {
+                None
+            } else {
+                setting
+                    .optional(&format!(
+                        "{NON_PRODUCTION_ANALYTICS_DOMAIN_KEY_PREFIX}{}",
+                        environment.as_str()
+                    ))
+                    .filter(|analytics_domain| !analytics_domain.is_empty())
+            };
+        let analytics_enabled: bool = setting.parsed(
+            "analytics_enabled",
+            environment.profile().analytics_enabled,
+            errors,
+            str::parse::<bool>,
+        );
+
+        // sentry DSN
+        let sentry_dsn: Option<Secret> = setting
+            .optional("sentry_dsn")
+            .filter(|sentry_dsn| !sentry_dsn.is_empty())
+            .map(Secret::new);
+
+        // IP anonymization (for logs)
+        let ip_anonymization: IpAnonymization = setting.parsed(
+            "ip_anonymization",
+            IpAnonymization::default(),
+            errors,
+            |s| IpAnonymization::try_from(s.to_string()),
+        );
+
+        // metrics: on an internal listener, or only to explicitly allowlisted clients
+        let metrics_listen: Option<ListenerConfig> =
+            setting.parsed("metrics_listen", None, errors, |s| s.parse().map(Some));
+        let metrics_allowlist: Vec<IpNetwork> = setting.parsed(
+            "metrics_allowlist",
+            Vec::new(),
+            errors,
+            |s| -> Result<Vec<IpNetwork>, String> {
+                s.split(',')
+                    .filter(|network| !network.trim().is_empty())
+                    .map(str::parse)
+                    .collect()
+            },
+        );
+
+        // all settings
+        BaseSettings {
+            host,
+            port,
+            listeners,
+            tls,
+            environment,
+            log_format,
+
+            project_name,
+            project_description,
+            project_keywords,
+            home_url,
+
+            analytics_domain,
+            non_production_analytics_domain,
+            analytics_enabled,
+            sentry_dsn,
+
+            ip_anonymization,
+
+            metrics_listen,
+            metrics_allowlist,
+        }
+    }
+}
+
+/// Reads merged setting values, recording missing/invalid ones.
+struct Setting<'a> {
+    values: &'a BTreeMap<String, String>,
+    env_prefix: &'a str,
+}
+
+impl Setting<'_> {
+    fn optional(&self, key: &str) -> Option<String> {
+        self.values.get(key).cloned()
+    }
+
+    fn required(&self, key: &str, errors: &mut Vec<SettingsError>) -> String {
+        self.optional(key).unwrap_or_else(|| {
+            errors.push(SettingsError::Missing {
+                key: key.to_string(),
+                env_var: format!("{}{}", self.env_prefix, key.to_uppercase()),
+            });
+            String::new()
+        })
+    }
+
+    fn parsed<T, E, F>(&self, key: &str, default: T, errors: &mut Vec<SettingsError>, parse: F) -> T
+    where
+        E: ToString,
+        F: FnOnce(&str) -> Result<T, E>,
+    {
+        let Some(value) = self.values.get(key) else {
+            return default;
+        };
+        parse(value).unwrap_or_else(|e| {
+            errors.push(SettingsError::Invalid {
+                key: key.to_string(),
+                value: value.clone(),
+                reason: e.to_string(),
+            });
+            default
+        })
+    }
+
+    /// The `listen` setting (e.g. `listen = "[::]:8080, unix:/run/site/site.sock?mode=660"`), defaulting to
+    /// `host:port`.
+    fn listeners(
+        &self,
+        host: &str,
+        port: u16,
+        errors: &mut Vec<SettingsError>,
+    ) -> Vec<ListenerConfig> {
+        let listeners: Vec<ListenerConfig> = self.parsed(
+            "listen",
+            Vec::new(),
+            errors,
+            |s| -> Result<Vec<ListenerConfig>, String> {
+                s.split(',')
+                    .filter(|listener| !listener.trim().is_empty())
+                    .map(str::parse)
+                    .collect()
+            },
+        );
+        if listeners.is_empty() {
+            match host.parse::<IpAddr>() {
+                Ok(ip) => vec![ListenerConfig::Tcp(SocketAddr::new(ip, port))],
+                Err(e) => {
+                    errors.push(SettingsError::Invalid {
+                        key: String::from("host"),
+                        value: host.to_string(),
+                        reason: e.to_string(),
+                    });
+                    Vec::new()
+                }
+            }
+        } else {
+            listeners
+        }
+    }
+
+    /// The TLS certificate and key (PEM), which `https://` listeners require.
+    fn tls(
+        &self,
+        listeners: &[ListenerConfig],
+        errors: &mut Vec<SettingsError>,
+    ) -> Option<TlsConfig> {
+        let tls: Option<TlsConfig> = match (
+            self.optional("tls_cert_path"),
+            self.optional("tls_key_path"),
+        ) {
+            (Some(cert_path), Some(key_path)) => Some(TlsConfig::new(cert_path, key_path)),
+            (None, None) => None,
+            (cert_path, _) => {
+                let key: &str = if cert_path.is_some() {
+                    "tls_key_path"
+                } else {
+                    "tls_cert_path"
+                };
+                errors.push(SettingsError::Missing {
+                    key: key.to_string(),
+                    env_var: format!("{}{}", self.env_prefix, key.to_uppercase()),
+                });
+                None
+            }
+        };
+        if tls.is_none()
+            && listeners
+                .iter()
+                .any(|listener| matches!(listener, ListenerConfig::Https(_)))
+        {
+            errors.push(SettingsError::Invalid {
+                key: String::from("listen"),
+                value: listeners
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<String>>()
+                    .join(","),
+                reason: String::from(
+                    "`https://` listeners require `tls_cert_path` and `tls_key_path`",
+                ),
+            });
+        }
+        tls
+    }
+}
+
+fn is_known_key(key: &str) -> bool {
+    KNOWN_KEYS.contains(&key) || key.starts_with(NON_PRODUCTION_ANALYTICS_DOMAIN_KEY_PREFIX)
+}
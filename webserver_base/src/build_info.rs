@@ -0,0 +1,71 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+/// Reported for anything the build script could not find out (e.g. the git commit, when building outside a checkout).
+pub const UNKNOWN: &str = "unknown";
+
+/// Which build of the server is running, as served by `/api/v1/version` and rendered in templates.
+///
+/// Create it with `build_info!()`, which reads the calling crate's version and the `BUILD_GIT_COMMIT`,
+/// `BUILD_TIMESTAMP` and `BUILD_RUSTC_VERSION` environment variables that `webserver_base_build::emit()` sets at compile time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    pub version: String,
+    pub git_commit: String,
+    pub build_timestamp: String,
+    pub rustc_version: String,
+}
+
+impl BuildInfo {
+    /// Values that were not captured are reported as `UNKNOWN`.
+    #[must_use]
+    pub fn new(
+        version: &str,
+        git_commit: Option<&str>,
+        build_timestamp: Option<&str>,
+        rustc_version: Option<&str>,
+    ) -> Self {
+        let known = |value: Option<&str>| -> String {
+            value
+                .filter(|value| !value.is_empty())
+                .unwrap_or(UNKNOWN)
+                .to_string()
+        };
+
+        Self {
+            version: version.to_string(),
+            git_commit: known(git_commit),
+            build_timestamp: known(build_timestamp),
+            rustc_version: known(rustc_version),
+        }
+    }
+}
+
+impl Display for BuildInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "version {} (commit {}, built {} with {})",
+            self.version, self.git_commit, self.build_timestamp, self.rustc_version
+        )
+    }
+}
+
+/// The `BuildInfo` of the crate that calls it.
+///
+/// Fails to compile unless the crate's build script calls `webserver_base_build::emit()`.
+#[macro_export]
+macro_rules! build_info {
+    () => {
+        $crate::build_info::BuildInfo::new(
+            env!("CARGO_PKG_VERSION"),
+            option_env!("BUILD_GIT_COMMIT"),
+            Some(env!(
+                "BUILD_TIMESTAMP",
+                "`build_info!()` needs a `build.rs` that calls `webserver_base_build::emit()`"
+            )),
+            option_env!("BUILD_RUSTC_VERSION"),
+        )
+    };
+}
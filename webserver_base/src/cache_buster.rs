@@ -9,7 +9,7 @@ use std::{fs::DirEntry, path::PathBuf};
 
 use axum::{
     body::Body,
-    extract::Request,
+    extract::{Request, State},
     http::{HeaderMap, HeaderValue},
     middleware::Next,
     response::Response,
@@ -25,6 +25,16 @@ use reqwest::{
 };
 use tracing::{error, instrument, warn};
 
+/// How long browsers may cache a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CachePolicy {
+    /// Cache for a year (only safe for cache-busted assets).
+    Forever,
+
+    /// Never cache.
+    NoCache,
+}
+
 #[derive(Debug, Clone)]
 pub struct CacheBuster {
     asset_directory: String,
@@ -83,6 +93,23 @@ impl CacheBuster {
         self.cache.clone()
     }
 
+    /// The number of cache-busted assets.
+    #[must_use]
+    pub fn asset_count(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// The combined size of the cache-busted assets on disk.
+    #[must_use]
+    #[instrument(skip_all)]
+    pub fn total_bytes(&self) -> u64 {
+        self.cache
+            .values()
+            .filter_map(|file_path| fs::metadata(file_path).ok())
+            .map(|metadata| metadata.len())
+            .sum()
+    }
+
     /// # Panics
     ///
     /// Panics if the file cannot be created or written to.
@@ -213,6 +240,23 @@ impl CacheBuster {
 
         Ok(response)
     }
+
+    /// Middleware that applies `policy` (e.g. the environment's `static_cache_policy`) to all responses.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Error` if the request cannot be processed.
+    #[instrument(skip_all)]
+    pub async fn cache_policy_middleware(
+        State(policy): State<CachePolicy>,
+        req: Request,
+        next: Next,
+    ) -> Result<Response, StatusCode> {
+        match policy {
+            CachePolicy::Forever => Self::forever_cache_middleware(req, next).await,
+            CachePolicy::NoCache => Self::never_cache_middleware(req, next).await,
+        }
+    }
 }
 
 impl Display for CacheBuster {
@@ -0,0 +1,310 @@
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+use chrono::{DateTime, Utc};
+use reqwest::Url;
+use sentry::protocol::{Breadcrumb, Context, Event, Exception, Frame, Level, Request, Stacktrace};
+use serde::{Deserialize, Deserializer, Serialize};
+use tracing::{debug, error, instrument};
+
+use super::{StackFrame, parse_stack_trace};
+use crate::base_settings::BaseSettings;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct FrontendErrorPayload {
+    source_file: Option<String>,
+    line_number: Option<i32>,
+    column_number: Option<i32>,
+
+    message: Option<String>,
+    stack_trace: Option<String>,
+    current_url: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_client_timestamp")]
+    timestamp: Option<DateTime<Utc>>,
+
+    #[serde(default)]
+    severity: Option<FrontendErrorSeverity>,
+    /// The error's `name` (e.g. "`TypeError`").
+    #[serde(default)]
+    name: Option<String>,
+    /// The browser's `navigator.userAgent`; the request's `User-Agent` header takes precedence.
+    #[serde(default)]
+    user_agent: Option<String>,
+    #[serde(default)]
+    viewport: Option<Viewport>,
+    /// What happened before the error, oldest first.
+    #[serde(default)]
+    breadcrumbs: Vec<FrontendBreadcrumb>,
+    /// Arbitrary application state (e.g. feature flags or the current route).
+    #[serde(default)]
+    context: BTreeMap<String, serde_json::Value>,
+}
+
+/// How severe a frontend error is; defaults to `Error`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrontendErrorSeverity {
+    Debug,
+    Info,
+    Warning,
+    #[default]
+    Error,
+    Fatal,
+}
+
+impl FrontendErrorSeverity {
+    #[must_use]
+    pub const fn to_sentry_level(self) -> Level {
+        match self {
+            Self::Debug => Level::Debug,
+            Self::Info => Level::Info,
+            Self::Warning => Level::Warning,
+            Self::Error => Level::Error,
+            Self::Fatal => Level::Fatal,
+        }
+    }
+}
+
+/// The browser window's inner size, in CSS pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Viewport {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Something that happened in the browser before the error (e.g. a click, navigation or network request).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct FrontendBreadcrumb {
+    #[serde(default, deserialize_with = "deserialize_client_timestamp")]
+    pub timestamp: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub severity: Option<FrontendErrorSeverity>,
+    #[serde(default)]
+    pub data: BTreeMap<String, serde_json::Value>,
+}
+
+impl FrontendBreadcrumb {
+    /// Converts to a Sentry breadcrumb.
+    #[must_use]
+    pub fn to_sentry_breadcrumb(&self) -> Breadcrumb {
+        Breadcrumb {
+            timestamp: self
+                .timestamp
+                .map_or_else(SystemTime::now, SystemTime::from),
+            category: self.category.clone(),
+            level: self
+                .severity
+                .unwrap_or(FrontendErrorSeverity::Info)
+                .to_sentry_level(),
+            message: self.message.clone(),
+            data: self.data.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+impl FrontendErrorPayload {
+    /// # Panics
+    ///
+    /// Panics if the `FrontendErrorPayload` cannot be serialized.
+    #[instrument(skip_all)]
+    pub fn log(&self) {
+        error!(
+            "{}",
+            serde_json::to_string_pretty(self).unwrap_or_else(|_| { panic!("{self:?}") })
+        );
+    }
+
+    /// Converts the payload into a Sentry event, using `frames` (innermost first) as its stack trace.
+    #[must_use]
+    #[instrument(skip_all)]
+    pub fn to_sentry_event(
+        &self,
+        user_agent: Option<&str>,
+        settings: &BaseSettings,
+        frames: &[StackFrame],
+    ) -> Event<'static> {
+        let message: String = self
+            .message
+            .clone()
+            .unwrap_or_else(|| String::from("<no message>"));
+
+        // Sentry expects frames oldest call first
+        let mut frames: Vec<Frame> = frames.iter().map(StackFrame::to_sentry_frame).collect();
+        frames.reverse();
+
+        let mut headers: BTreeMap<String, String> = BTreeMap::new();
+        if let Some(user_agent) = user_agent {
+            headers.insert(String::from("User-Agent"), user_agent.to_string());
+        }
+
+        let mut tags: BTreeMap<String, String> = BTreeMap::new();
+        tags.insert(String::from("source"), String::from("frontend"));
+        tags.insert(
+            String::from("environment"),
+            settings.environment.to_string(),
+        );
+
+        let mut extra: BTreeMap<String, serde_json::Value> = BTreeMap::new();
+        if let Some(timestamp) = &self.timestamp {
+            extra.insert(
+                String::from("client_timestamp"),
+                serde_json::Value::from(timestamp.to_rfc3339()),
+            );
+        }
+        if let Some(viewport) = &self.viewport {
+            extra.insert(
+                String::from("viewport"),
+                serde_json::Value::from(format!("{}x{}", viewport.width, viewport.height)),
+            );
+        }
+
+        let mut contexts: BTreeMap<String, Context> = BTreeMap::new();
+        if !self.context.is_empty() {
+            contexts.insert(
+                String::from("frontend"),
+                Context::Other(self.context.clone()),
+            );
+        }
+
+        Event {
+            level: self.severity.unwrap_or_default().to_sentry_level(),
+            platform: "javascript".into(),
+            logger: Some(String::from("frontend")),
+            culprit: self.current_url.clone(),
+            message: Some(message.clone()),
+            exception: vec![Exception {
+                ty: self.name.clone().unwrap_or_else(|| error_type(&message)),
+                value: Some(message),
+                stacktrace: (!frames.is_empty()).then(|| Stacktrace {
+                    frames,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }]
+            .into(),
+            request: Some(Request {
+                url: self
+                    .current_url
+                    .as_deref()
+                    .and_then(|current_url| Url::parse(current_url).ok()),
+                headers,
+                ..Default::default()
+            }),
+            release: sentry::Hub::current()
+                .client()
+                .and_then(|client| client.options().release.clone()),
+            environment: Some(settings.environment.to_string().into()),
+            tags,
+            extra,
+            contexts,
+            breadcrumbs: self
+                .breadcrumbs
+                .iter()
+                .map(FrontendBreadcrumb::to_sentry_breadcrumb)
+                .collect::<Vec<Breadcrumb>>()
+                .into(),
+            ..Default::default()
+        }
+    }
+
+    #[must_use]
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    #[must_use]
+    pub fn current_url(&self) -> Option<&str> {
+        self.current_url.as_deref()
+    }
+
+    #[must_use]
+    pub fn user_agent(&self) -> Option<&str> {
+        self.user_agent.as_deref()
+    }
+
+    #[must_use]
+    pub const fn timestamp(&self) -> Option<DateTime<Utc>> {
+        self.timestamp
+    }
+
+    #[must_use]
+    pub fn severity(&self) -> FrontendErrorSeverity {
+        self.severity.unwrap_or_default()
+    }
+
+    /// The parsed `stack_trace` (innermost first), falling back to the reported source location when there is no stack trace.
+    #[must_use]
+    pub fn stack_frames(&self) -> Vec<StackFrame> {
+        let frames: Vec<StackFrame> = self
+            .stack_trace
+            .as_deref()
+            .map(parse_stack_trace)
+            .unwrap_or_default();
+        if !frames.is_empty() {
+            return frames;
+        }
+
+        match (&self.source_file, self.line_number, self.column_number) {
+            (Some(source_file), Some(line_number), column_number) => {
+                vec![StackFrame {
+                    function: None,
+                    file: source_file.clone(),
+                    line: u32::try_from(line_number).unwrap_or_default(),
+                    column: column_number
+                        .and_then(|column_number| u32::try_from(column_number).ok())
+                        .unwrap_or_default(),
+                }]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Extracts the error type from messages like "Uncaught `TypeError`: x is undefined".
+fn error_type(message: &str) -> String {
+    message
+        .trim_start_matches("Uncaught ")
+        .split_once(':')
+        .map(|(ty, _)| ty.trim())
+        .filter(|ty| !ty.is_empty() && !ty.contains(' '))
+        .unwrap_or("Error")
+        .to_string()
+}
+
+/// Parses RFC 3339 timestamps (`Date.toISOString()`), as well as the `Date.toString()` format that older clients send
+/// (e.g. "Mon Oct 19 2026 12:34:56 GMT+0200 (Central European Summer Time)").
+///
+/// Unparseable timestamps are dropped rather than rejecting the whole error report.
+fn deserialize_client_timestamp<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let Some(timestamp) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+
+    let parsed: Option<DateTime<Utc>> = DateTime::parse_from_rfc3339(&timestamp)
+        .or_else(|_| {
+            // drop the trailing "(time zone name)"
+            let without_zone_name: &str = timestamp
+                .split_once(" (")
+                .map_or(timestamp.as_str(), |(without_zone_name, _)| {
+                    without_zone_name
+                });
+            DateTime::parse_from_str(without_zone_name, "%a %b %d %Y %H:%M:%S GMT%z")
+        })
+        .ok()
+        .map(|timestamp| timestamp.with_timezone(&Utc));
+    if parsed.is_none() {
+        debug!("dropping unparseable frontend error timestamp: {timestamp:?}");
+    }
+    Ok(parsed)
+}
@@ -0,0 +1,157 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use axum::http::{HeaderMap, header::USER_AGENT};
+use tracing::{debug, instrument, warn};
+
+use super::frontend_error_throttle::FrontendErrorThrottle;
+use super::{FrontendErrorPayload, FrontendErrorThrottleConfig, SourceMapResolver, StackFrame};
+use crate::base_settings::BaseSettings;
+use crate::ip::{IpAnonymizer, resolve_true_client_ip_address};
+use crate::request_id::RequestId;
+
+/// Request body limit for the frontend error route; stack traces easily exceed the server-wide body limit.
+pub const FRONTEND_ERROR_BODY_LIMIT: usize = 64 * 1024;
+
+/// How many frontend errors were received since the server started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FrontendErrorStats {
+    pub reported: u64,
+    pub throttled: u64,
+}
+
+/// Reports frontend errors to Sentry as structured events, with stack frames resolved through source maps.
+///
+/// Repeated reports are throttled (see `FrontendErrorThrottleConfig`).
+pub struct FrontendErrorReporter {
+    settings: BaseSettings,
+    source_maps: SourceMapResolver,
+    throttle: FrontendErrorThrottle,
+    ip_anonymizer: IpAnonymizer,
+    reported: AtomicU64,
+    throttled: AtomicU64,
+}
+
+impl FrontendErrorReporter {
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime (the throttle flushes its windows from a background task).
+    #[must_use]
+    pub fn new(settings: BaseSettings, source_maps: SourceMapResolver) -> Self {
+        Self {
+            ip_anonymizer: IpAnonymizer::new(settings.ip_anonymization),
+            settings,
+            source_maps,
+            throttle: FrontendErrorThrottle::new(FrontendErrorThrottleConfig::default()),
+            reported: AtomicU64::new(0),
+            throttled: AtomicU64::new(0),
+        }
+    }
+
+    /// Replaces the default throttling limits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    #[must_use]
+    pub fn throttle(mut self, config: FrontendErrorThrottleConfig) -> Self {
+        self.throttle = FrontendErrorThrottle::new(config);
+        self
+    }
+
+    #[must_use]
+    pub fn stats(&self) -> FrontendErrorStats {
+        FrontendErrorStats {
+            reported: self.reported.load(Ordering::Relaxed),
+            throttled: self.throttled.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Reports the error to Sentry (parsed and symbolicated stack frames, page URL, User-Agent, request ID, release and
+    /// environment), and returns the Sentry event ID, or `None` if the report was throttled.
+    ///
+    /// The error is only logged at `WARN` level (which the Sentry tracing integration records as a breadcrumb), so it is
+    /// not reported twice. Throttled reports are rejected before their stack frames are symbolicated.
+    #[instrument(skip_all)]
+    pub fn report(
+        &self,
+        headers: &HeaderMap,
+        addr: SocketAddr,
+        payload: &FrontendErrorPayload,
+    ) -> Option<sentry::types::Uuid> {
+        let user_agent: Option<&str> = headers
+            .get(USER_AGENT)
+            .and_then(|user_agent| user_agent.to_str().ok())
+            .or_else(|| payload.user_agent());
+
+        let message: &str = payload.message().unwrap_or("<no message>");
+        let minified_frames: Vec<StackFrame> = payload.stack_frames();
+
+        let fingerprint: u64 = fingerprint(message, minified_frames.first());
+        if !self
+            .throttle
+            .should_report(fingerprint, self.client_key(addr, headers), message)
+        {
+            debug!("throttled report of frontend error {fingerprint:016x}");
+            self.throttled.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+
+        let frames: Vec<StackFrame> = minified_frames
+            .iter()
+            .map(|frame| self.source_maps.resolve(frame))
+            .collect();
+
+        let request_id: Option<RequestId> = RequestId::from_headers(headers);
+        warn!(
+            request_id = request_id.as_ref().map(RequestId::as_str),
+            "frontend error {fingerprint:016x} on {:?} at {}: {message}",
+            payload.current_url(),
+            frames.first().map_or_else(
+                || String::from("<unknown location>"),
+                |frame| format!("{}:{}:{}", frame.file, frame.line, frame.column)
+            ),
+        );
+        let mut event = payload.to_sentry_event(user_agent, &self.settings, &frames);
+        event.fingerprint = vec![format!("{fingerprint:016x}").into()].into();
+        if let Some(request_id) = request_id {
+            event
+                .tags
+                .insert(String::from("request_id"), request_id.to_string());
+        }
+        self.reported.fetch_add(1, Ordering::Relaxed);
+        Some(sentry::capture_event(event))
+    }
+
+    /// Identifies a client for throttling by a single IP address, so that varying the forwarding headers does not make
+    /// a client look new: the resolved client IP address behind a reverse proxy on a loopback or private address (the
+    /// only peers whose forwarding headers are trusted), otherwise the peer address.
+    fn client_key(&self, addr: SocketAddr, headers: &HeaderMap) -> u64 {
+        let mut hasher: DefaultHasher = DefaultHasher::new();
+        if is_reverse_proxy(addr.ip()) {
+            resolve_true_client_ip_address(addr, headers, &self.ip_anonymizer).hash(&mut hasher);
+        } else {
+            addr.ip().to_string().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
+
+/// Groups reports of the same error: its message and the location that threw it, as reported by the browser (the
+/// location in the minified script, which is the same for every report until the script changes).
+fn fingerprint(message: &str, top_frame: Option<&StackFrame>) -> u64 {
+    let mut hasher: DefaultHasher = DefaultHasher::new();
+    message.hash(&mut hasher);
+    if let Some(top_frame) = top_frame {
+        (&top_frame.file, top_frame.line, top_frame.column).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+fn is_reverse_proxy(ip: IpAddr) -> bool {
+    match ip.to_canonical() {
+        IpAddr::V4(ip) => ip.is_loopback() || ip.is_private(),
+        IpAddr::V6(ip) => ip.is_loopback() || ip.is_unique_local(),
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use std::time::{Duration, Instant};
+
+use tokio::time::{Interval, MissedTickBehavior};
+use tracing::{instrument, warn};
+
+/// Distinct errors tracked per window; reports of any further errors are suppressed until the window ends.
+const MAX_TRACKED_FINGERPRINTS: usize = 1024;
+
+/// Clients tracked per window; reports from any further clients are suppressed until the window ends.
+const MAX_TRACKED_CLIENTS: usize = 4096;
+
+/// Length of the message excerpt kept for the "suppressed N reports" summary.
+const MAX_MESSAGE_CHARS: usize = 200;
+
+/// Limits on how many frontend error reports are forwarded, so a single broken page cannot flood the logs and Sentry.
+#[derive(Debug, Clone, Copy)]
+pub struct FrontendErrorThrottleConfig {
+    /// Length of the window that the limits below apply to.
+    pub window: Duration,
+
+    /// Maximum number of reports of the same error (message + top stack frame) per window.
+    pub max_reports_per_fingerprint: u32,
+
+    /// Maximum number of reports from the same client IP address per window, across all errors.
+    pub max_reports_per_client: u32,
+
+    /// Fraction (0.0 to 1.0) of the reports within the limits above that are forwarded.
+    pub sample_rate: f64,
+}
+
+impl Default for FrontendErrorThrottleConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_mins(1),
+            max_reports_per_fingerprint: 1,
+            max_reports_per_client: 10,
+            sample_rate: 1.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct FingerprintWindow {
+    started_at: Instant,
+    message: String,
+    reported: u32,
+    suppressed: u64,
+}
+
+impl FingerprintWindow {
+    fn new(now: Instant, message: &str) -> Self {
+        Self {
+            started_at: now,
+            message: message.chars().take(MAX_MESSAGE_CHARS).collect(),
+            reported: 0,
+            suppressed: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ClientWindow {
+    started_at: Instant,
+    received: u32,
+}
+
+#[derive(Debug, Default)]
+struct Windows {
+    fingerprints: HashMap<u64, FingerprintWindow>,
+    clients: HashMap<u64, ClientWindow>,
+
+    // reports suppressed because `MAX_TRACKED_FINGERPRINTS` errors were already tracked
+    untracked_suppressed: u64,
+
+    // reports suppressed because `MAX_TRACKED_CLIENTS` clients were already tracked
+    untracked_clients_suppressed: u64,
+}
+
+/// Time-windowed counters of reported and suppressed frontend errors, per fingerprint and per client.
+#[derive(Debug)]
+pub(crate) struct FrontendErrorThrottle {
+    config: FrontendErrorThrottleConfig,
+    windows: Arc<Mutex<Windows>>,
+}
+
+impl FrontendErrorThrottle {
+    /// Spawns a task that, once per window, drops the expired windows and logs how many reports were suppressed
+    /// during them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    pub fn new(config: FrontendErrorThrottleConfig) -> Self {
+        let windows: Arc<Mutex<Windows>> = Arc::new(Mutex::new(Windows::default()));
+        tokio::spawn(flush_periodically(Arc::downgrade(&windows), config.window));
+        Self { config, windows }
+    }
+
+    /// Records an occurrence of the error and returns whether it should be reported.
+    ///
+    /// The client is charged first, so that a client over its limit cannot make the throttle track more errors.
+    #[instrument(skip_all)]
+    pub fn should_report(&self, fingerprint: u64, client: u64, message: &str) -> bool {
+        let now: Instant = Instant::now();
+        let window: Duration = self.config.window;
+        let mut windows = self.windows.lock().unwrap_or_else(PoisonError::into_inner);
+        let windows: &mut Windows = &mut windows;
+
+        if windows.clients.len() >= MAX_TRACKED_CLIENTS && !windows.clients.contains_key(&client) {
+            windows
+                .clients
+                .retain(|_, client_window| now.duration_since(client_window.started_at) < window);
+            if windows.clients.len() >= MAX_TRACKED_CLIENTS {
+                windows.untracked_clients_suppressed += 1;
+                return false;
+            }
+        }
+        let client_window: &mut ClientWindow =
+            windows
+                .clients
+                .entry(client)
+                .or_insert_with(|| ClientWindow {
+                    started_at: now,
+                    received: 0,
+                });
+        if now.duration_since(client_window.started_at) >= window {
+            client_window.started_at = now;
+            client_window.received = 0;
+        }
+        client_window.received = client_window.received.saturating_add(1);
+        if client_window.received > self.config.max_reports_per_client {
+            if let Some(fingerprint_window) = windows.fingerprints.get_mut(&fingerprint) {
+                fingerprint_window.suppressed += 1;
+            }
+            return false;
+        }
+
+        let tracking_full: bool = windows.fingerprints.len() >= MAX_TRACKED_FINGERPRINTS;
+        let fingerprint_window: &mut FingerprintWindow =
+            match windows.fingerprints.entry(fingerprint) {
+                Entry::Occupied(entry) => {
+                    let fingerprint_window: &mut FingerprintWindow = entry.into_mut();
+                    if now.duration_since(fingerprint_window.started_at) >= window {
+                        log_suppressed(fingerprint, fingerprint_window, window);
+                        *fingerprint_window = FingerprintWindow::new(now, message);
+                    }
+                    fingerprint_window
+                }
+                Entry::Vacant(_) if tracking_full => {
+                    windows.untracked_suppressed += 1;
+                    return false;
+                }
+                Entry::Vacant(entry) => entry.insert(FingerprintWindow::new(now, message)),
+            };
+
+        if fingerprint_window.reported >= self.config.max_reports_per_fingerprint
+            || rand::random::<f64>() >= self.config.sample_rate
+        {
+            fingerprint_window.suppressed += 1;
+            return false;
+        }
+        fingerprint_window.reported += 1;
+        true
+    }
+}
+
+async fn flush_periodically(windows: Weak<Mutex<Windows>>, window: Duration) {
+    let mut interval: Interval = tokio::time::interval(window.max(Duration::from_secs(1)));
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    // the first tick completes immediately
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+        // the throttle was dropped
+        let Some(windows) = windows.upgrade() else {
+            return;
+        };
+        flush(
+            &mut windows.lock().unwrap_or_else(PoisonError::into_inner),
+            Instant::now(),
+            window,
+        );
+    }
+}
+
+fn flush(windows: &mut Windows, now: Instant, window: Duration) {
+    windows
+        .fingerprints
+        .retain(|fingerprint, fingerprint_window| {
+            let expired: bool = now.duration_since(fingerprint_window.started_at) >= window;
+            if expired {
+                log_suppressed(*fingerprint, fingerprint_window, window);
+            }
+            !expired
+        });
+    windows
+        .clients
+        .retain(|_, client_window| now.duration_since(client_window.started_at) < window);
+
+    if windows.untracked_suppressed > 0 {
+        warn!(
+            "suppressed {} reports of frontend errors because {MAX_TRACKED_FINGERPRINTS} distinct errors were already reported in the last {window:?}",
+            windows.untracked_suppressed
+        );
+        windows.untracked_suppressed = 0;
+    }
+    if windows.untracked_clients_suppressed > 0 {
+        warn!(
+            "suppressed {} reports of frontend errors because {MAX_TRACKED_CLIENTS} clients already reported errors in the last {window:?}",
+            windows.untracked_clients_suppressed
+        );
+        windows.untracked_clients_suppressed = 0;
+    }
+}
+
+fn log_suppressed(fingerprint: u64, fingerprint_window: &FingerprintWindow, window: Duration) {
+    if fingerprint_window.suppressed > 0 {
+        warn!(
+            "suppressed {} reports of frontend error {fingerprint:016x} ({:?}) in the last {window:?}",
+            fingerprint_window.suppressed, fingerprint_window.message
+        );
+    }
+}
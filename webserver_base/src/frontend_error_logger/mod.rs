@@ -0,0 +1,11 @@
+mod frontend_error_payload;
+mod frontend_error_reporter;
+mod frontend_error_throttle;
+mod source_map;
+mod stack_trace;
+
+pub use frontend_error_payload::*;
+pub use frontend_error_reporter::*;
+pub use frontend_error_throttle::FrontendErrorThrottleConfig;
+pub use source_map::*;
+pub use stack_trace::*;
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::Path;
+
+use reqwest::Url;
+use sourcemap::{SourceMap, SourceView, Token};
+use tracing::{error, info, instrument};
+
+use super::StackFrame;
+use crate::cache_buster::CacheBuster;
+
+/// A minified script and its parsed source map.
+#[derive(Debug)]
+struct Script {
+    source_map: SourceMap,
+    minified_source: SourceView,
+}
+
+/// Resolves minified frontend stack frames to their original (TypeScript) locations using the bundler's source maps.
+#[derive(Debug, Default)]
+pub struct SourceMapResolver {
+    // cache-busted script path without a leading slash (e.g. "static/script/main.<hash>.js") -> the script
+    scripts: HashMap<String, Script>,
+}
+
+impl SourceMapResolver {
+    /// Loads the source map of every `.js` file that has a corresponding `.js.map` file in the `CacheBuster`.
+    ///
+    /// Source maps that cannot be read or parsed are logged and skipped.
+    #[must_use]
+    #[instrument(skip_all)]
+    pub fn new(cache_buster: &CacheBuster) -> Self {
+        let cache = cache_buster.get_cache();
+
+        let mut scripts: HashMap<String, Script> = HashMap::new();
+        for (original_path, hashed_path) in &cache {
+            if !Path::new(original_path)
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("js"))
+            {
+                continue;
+            }
+            let Some(hashed_map_path) = cache.get(&format!("{original_path}.map")) else {
+                continue;
+            };
+
+            match load_script(hashed_path, hashed_map_path) {
+                Ok(script) => {
+                    scripts.insert(hashed_path.trim_start_matches('/').to_string(), script);
+                }
+                Err(e) => error!("failed to load source map '{hashed_map_path}': {e}"),
+            }
+        }
+        info!("loaded {} source maps", scripts.len());
+
+        Self { scripts }
+    }
+
+    /// Resolves a frame through its script's source map, or returns it unchanged if it cannot be resolved.
+    #[must_use]
+    #[instrument(skip_all)]
+    pub fn resolve(&self, frame: &StackFrame) -> StackFrame {
+        self.try_resolve(frame).unwrap_or_else(|| frame.clone())
+    }
+
+    fn try_resolve(&self, frame: &StackFrame) -> Option<StackFrame> {
+        // frames reference scripts by URL (e.g. "https://example.com/static/script/main.<hash>.js")
+        let path: String = Url::parse(&frame.file)
+            .map_or_else(|_| frame.file.clone(), |url| url.path().to_string());
+        let script: &Script = self.scripts.get(path.trim_start_matches('/'))?;
+
+        // browsers report 1-based lines and columns; source maps are 0-based
+        let (line, column): (u32, u32) = (frame.line.checked_sub(1)?, frame.column.checked_sub(1)?);
+        let token: Token<'_> = script.source_map.lookup_token(line, column)?;
+
+        // the token's own name is whatever identifier the error is at, not the enclosing function: look for the
+        // minified function's declaration instead, and keep the minified name if it cannot be found
+        let function: Option<String> = frame.function.as_deref().map(|minified_name| {
+            script
+                .source_map
+                .get_original_function_name(line, column, minified_name, &script.minified_source)
+                .unwrap_or(minified_name)
+                .to_string()
+        });
+
+        Some(StackFrame {
+            function,
+            file: token.get_source()?.to_string(),
+            line: token.get_src_line() + 1,
+            column: token.get_src_col() + 1,
+        })
+    }
+}
+
+fn load_script(path: &str, map_path: &str) -> Result<Script, String> {
+    let source_map: SourceMap = File::open(map_path)
+        .map_err(|e| e.to_string())
+        .and_then(|file| SourceMap::from_reader(file).map_err(|e| e.to_string()))?;
+    let minified_source: String = fs::read_to_string(path).map_err(|e| format!("'{path}': {e}"))?;
+    Ok(Script {
+        source_map,
+        minified_source: SourceView::from_string(minified_source),
+    })
+}
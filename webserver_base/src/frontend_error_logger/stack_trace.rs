@@ -0,0 +1,75 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+// Chrome/V8/Edge: "    at functionName (https://example.com/static/script/main.js:1:2)" or "    at https://...:1:2"
+static V8_FRAME_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*at (?:(.+?) \()?(.+?):(\d+):(\d+)\)?\s*$")
+        .unwrap_or_else(|_| panic!("Failed to compile V8 stack frame regex"))
+});
+
+// Firefox/Safari: "functionName@https://example.com/static/script/main.js:1:2"
+static GECKO_FRAME_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(.*?)@(.+?):(\d+):(\d+)\s*$")
+        .unwrap_or_else(|_| panic!("Failed to compile Gecko stack frame regex"))
+});
+
+/// A single frame of a browser's `Error.stack`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StackFrame {
+    pub function: Option<String>,
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl StackFrame {
+    /// Converts to a Sentry frame.
+    #[must_use]
+    pub fn to_sentry_frame(&self) -> sentry::protocol::Frame {
+        sentry::protocol::Frame {
+            function: self.function.clone(),
+            abs_path: Some(self.file.clone()),
+            filename: Some(
+                self.file
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or(self.file.as_str())
+                    .to_string(),
+            ),
+            lineno: Some(u64::from(self.line)),
+            colno: Some(u64::from(self.column)),
+            in_app: Some(true),
+            ..Default::default()
+        }
+    }
+}
+
+/// Parses a browser's `Error.stack` (V8 and Gecko/WebKit formats) into frames, innermost (most recent call) first.
+///
+/// Lines that are not frames (e.g. the "`TypeError`: ..." header) are skipped.
+#[must_use]
+#[instrument(skip_all)]
+pub fn parse_stack_trace(stack_trace: &str) -> Vec<StackFrame> {
+    stack_trace
+        .lines()
+        .filter_map(|line| {
+            V8_FRAME_REGEX
+                .captures(line)
+                .or_else(|| GECKO_FRAME_REGEX.captures(line))
+        })
+        .filter_map(|captures| {
+            Some(StackFrame {
+                function: captures
+                    .get(1)
+                    .map(|function| function.as_str().trim().to_string())
+                    .filter(|function| !function.is_empty()),
+                file: captures.get(2)?.as_str().to_string(),
+                line: captures.get(3)?.as_str().parse().ok()?,
+                column: captures.get(4)?.as_str().parse().ok()?,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,20 @@
+use std::future::Future;
+use std::pin::Pin;
+
+pub type HealthCheckFuture<'a> = Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>>;
+
+/// Something the server depends on at runtime (a database, a queue, an upstream API, ...), as reported by the
+/// `/api/v1/health/ready` endpoint.
+///
+/// Checks run on every request to the endpoint, so they should probe live state; anything that cannot change once the
+/// server has started (e.g. loaded templates) is better verified at startup, and only reported here (e.g.
+/// `TemplateRegistryCheck`).
+///
+/// Register checks with `HealthChecks::check`, or `HealthChecks::informational` if the server can serve without them.
+pub trait HealthCheck: Send + Sync {
+    /// Short name of the check, reported in the endpoint's response.
+    fn name(&self) -> &'static str;
+
+    /// Resolves to `Err` with the reason if the dependency is unhealthy.
+    fn check(&self) -> HealthCheckFuture<'_>;
+}
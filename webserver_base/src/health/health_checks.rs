@@ -0,0 +1,162 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tracing::{instrument, warn};
+
+use super::HealthCheck;
+
+/// Whether a check (or the server as a whole) is healthy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Pass,
+
+    /// Degraded: an informational check failed, but the server can still serve traffic.
+    Warn,
+
+    Fail,
+}
+
+/// The outcome of a single `HealthCheck`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CheckReport {
+    pub name: &'static str,
+    pub status: HealthStatus,
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The body of the `/api/v1/health/ready` endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HealthReport {
+    /// `Fail` if the server is not serving (see `Readiness`) or a check failed, `Warn` if only informational checks
+    /// failed, otherwise `Pass`.
+    pub status: HealthStatus,
+
+    /// Whether the server has started up and is not shutting down.
+    pub serving: bool,
+
+    pub checks: Vec<CheckReport>,
+}
+
+/// A registered `HealthCheck`, and whether its failure makes the server unready.
+#[derive(Clone)]
+struct RegisteredCheck {
+    check: Arc<dyn HealthCheck>,
+    fails_readiness: bool,
+}
+
+/// The `HealthCheck`s that decide whether the server is ready for traffic. Clones share the same checks.
+#[derive(Clone)]
+pub struct HealthChecks {
+    checks: Vec<RegisteredCheck>,
+    timeout: Duration,
+}
+
+impl Debug for HealthChecks {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HealthChecks")
+            .field(
+                "checks",
+                &self
+                    .checks
+                    .iter()
+                    .map(|registered| registered.check.name())
+                    .collect::<Vec<&str>>(),
+            )
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl Default for HealthChecks {
+    fn default() -> Self {
+        Self {
+            checks: Vec::new(),
+            timeout: Duration::from_secs(1),
+        }
+    }
+}
+
+impl HealthChecks {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a check that the server cannot serve traffic without: while it fails, the server is not ready.
+    #[must_use]
+    pub fn check(mut self, check: impl HealthCheck + 'static) -> Self {
+        self.checks.push(RegisteredCheck {
+            check: Arc::new(check),
+            fails_readiness: true,
+        });
+        self
+    }
+
+    /// Registers a check that is only reported: while it fails, the server is degraded (`HealthStatus::Warn`) but
+    /// still ready, so that e.g. a slow analytics backend does not take the site out of rotation.
+    #[must_use]
+    pub fn informational(mut self, check: impl HealthCheck + 'static) -> Self {
+        self.checks.push(RegisteredCheck {
+            check: Arc::new(check),
+            fails_readiness: false,
+        });
+        self
+    }
+
+    /// How long a check may take before it fails (default: 1 second).
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Runs every check, in the order they were registered.
+    #[instrument(skip_all)]
+    pub async fn run(&self, serving: bool) -> HealthReport {
+        let mut checks: Vec<CheckReport> = Vec::with_capacity(self.checks.len());
+        for RegisteredCheck {
+            check,
+            fails_readiness,
+        } in &self.checks
+        {
+            let start: Instant = Instant::now();
+            let result: Result<(), String> = tokio::time::timeout(self.timeout, check.check())
+                .await
+                .unwrap_or_else(|_| Err(format!("timed out after {:?}", self.timeout)));
+            let latency_ms: f64 = start.elapsed().as_secs_f64() * 1000.0;
+
+            if let Err(e) = &result {
+                warn!("health check '{}' failed: {e}", check.name());
+            }
+            checks.push(CheckReport {
+                name: check.name(),
+                status: match (&result, fails_readiness) {
+                    (Ok(()), _) => HealthStatus::Pass,
+                    (Err(_), true) => HealthStatus::Fail,
+                    (Err(_), false) => HealthStatus::Warn,
+                },
+                latency_ms,
+                error: result.err(),
+            });
+        }
+
+        let status: HealthStatus =
+            if !serving || checks.iter().any(|c| c.status == HealthStatus::Fail) {
+                HealthStatus::Fail
+            } else if checks.iter().any(|c| c.status == HealthStatus::Warn) {
+                HealthStatus::Warn
+            } else {
+                HealthStatus::Pass
+            };
+        HealthReport {
+            status,
+            serving,
+            checks,
+        }
+    }
+}
@@ -0,0 +1,7 @@
+mod health_check;
+mod health_checks;
+mod subsystem_checks;
+
+pub use health_check::*;
+pub use health_checks::*;
+pub use subsystem_checks::*;
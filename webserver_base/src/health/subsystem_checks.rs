@@ -0,0 +1,121 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use super::{HealthCheck, HealthCheckFuture};
+use crate::axum_plausible_analytics::AxumPlausibleAnalyticsHandler;
+use crate::cache_buster::CacheBuster;
+use crate::templates::template_registry::TemplateRegistry;
+
+/// Fails if no templates were loaded (e.g. the server was started outside the directory holding `html/`).
+///
+/// The templates do not change once loaded, so also verify them at startup, and register this with
+/// `HealthChecks::informational` to report them.
+#[derive(Debug, Clone, Copy)]
+pub struct TemplateRegistryCheck {
+    template_count: usize,
+}
+
+impl TemplateRegistryCheck {
+    #[must_use]
+    pub fn new(template_registry: &TemplateRegistry<'_>) -> Self {
+        Self {
+            template_count: template_registry.template_count(),
+        }
+    }
+}
+
+impl HealthCheck for TemplateRegistryCheck {
+    fn name(&self) -> &'static str {
+        "templates"
+    }
+
+    fn check(&self) -> HealthCheckFuture<'_> {
+        let template_count: usize = self.template_count;
+        Box::pin(async move {
+            if template_count == 0 {
+                return Err(String::from("no templates are loaded"));
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Fails if the cache-busted asset cache is empty (e.g. `CacheBuster::gen_cache` was not called).
+///
+/// Like `TemplateRegistryCheck`, this reports state that is fixed at startup.
+#[derive(Debug, Clone, Copy)]
+pub struct CacheBusterCheck {
+    asset_count: usize,
+}
+
+impl CacheBusterCheck {
+    #[must_use]
+    pub fn new(cache_buster: &CacheBuster) -> Self {
+        Self {
+            asset_count: cache_buster.asset_count(),
+        }
+    }
+}
+
+impl HealthCheck for CacheBusterCheck {
+    fn name(&self) -> &'static str {
+        "cache_buster"
+    }
+
+    fn check(&self) -> HealthCheckFuture<'_> {
+        let asset_count: usize = self.asset_count;
+        Box::pin(async move {
+            if asset_count == 0 {
+                return Err(String::from("no static assets are cached"));
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Fails while more than `max_queued` analytics events are waiting to be sent, which means the analytics backend is
+/// down or too slow and events will soon be dropped.
+///
+/// Pages are still served without analytics, so register it with `HealthChecks::informational`.
+#[derive(Clone)]
+pub struct AnalyticsQueueCheck {
+    analytics: Arc<AxumPlausibleAnalyticsHandler>,
+    max_queued: usize,
+}
+
+impl Debug for AnalyticsQueueCheck {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AnalyticsQueueCheck")
+            .field("max_queued", &self.max_queued)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AnalyticsQueueCheck {
+    #[must_use]
+    pub fn new(analytics: Arc<AxumPlausibleAnalyticsHandler>, max_queued: usize) -> Self {
+        Self {
+            analytics,
+            max_queued,
+        }
+    }
+}
+
+impl HealthCheck for AnalyticsQueueCheck {
+    fn name(&self) -> &'static str {
+        "analytics_queue"
+    }
+
+    fn check(&self) -> HealthCheckFuture<'_> {
+        Box::pin(async move {
+            let queued: usize = self.analytics.stats().queued;
+            if queued > self.max_queued {
+                return Err(format!(
+                    "{queued} events are queued (more than {})",
+                    self.max_queued
+                ));
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,159 @@
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Mutex;
+
+use chrono::{NaiveDate, Utc};
+use sha2::{Digest, Sha256};
+use tracing::instrument;
+
+/// How client IP addresses are anonymised before they are written to logs (and therefore Sentry breadcrumbs).
+///
+/// This never affects the IP address forwarded to Plausible Analytics, which needs the full address to count unique visitors.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum IpAnonymization {
+    /// Log full IP addresses.
+    Disabled,
+
+    /// Truncate IPv4 addresses to their /24 network and IPv6 addresses to their /48 network.
+    #[default]
+    Truncate,
+
+    /// Replace IP addresses with a keyed hash whose salt is regenerated every (UTC) day.
+    Hash,
+}
+
+impl IpAnonymization {
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Disabled => "disabled",
+            Self::Truncate => "truncate",
+            Self::Hash => "hash",
+        }
+    }
+}
+
+impl TryFrom<String> for IpAnonymization {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        match s.to_lowercase().as_str() {
+            "disabled" => Ok(Self::Disabled),
+            "truncate" => Ok(Self::Truncate),
+            "hash" => Ok(Self::Hash),
+            other => Err(format!(
+                "{other} is not a supported IP anonymization. Use either `disabled`, `truncate`, or `hash`."
+            )),
+        }
+    }
+}
+
+impl Display for IpAnonymization {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Anonymises IP addresses according to an `IpAnonymization` policy.
+///
+/// Holds the daily salt used by `IpAnonymization::Hash`, so a single instance should be shared for the lifetime of the
+/// server. The salt only ever lives in memory, so hashes cannot be correlated across days or restarts.
+#[derive(Debug)]
+pub struct IpAnonymizer {
+    anonymization: IpAnonymization,
+
+    daily_salt: Mutex<Option<(NaiveDate, [u8; 32])>>,
+}
+
+impl IpAnonymizer {
+    #[must_use]
+    pub const fn new(anonymization: IpAnonymization) -> Self {
+        Self {
+            anonymization,
+            daily_salt: Mutex::new(None),
+        }
+    }
+
+    #[must_use]
+    pub const fn anonymization(&self) -> IpAnonymization {
+        self.anonymization
+    }
+
+    /// Anonymises a single IP address.
+    ///
+    /// Values that are not valid IP addresses are hashed when hashing is enabled, and redacted entirely when truncating
+    /// (since there is no network portion to keep).
+    #[must_use]
+    #[instrument(skip_all)]
+    pub fn anonymize(&self, ip_address: &str) -> String {
+        match self.anonymization {
+            IpAnonymization::Disabled => ip_address.to_string(),
+            IpAnonymization::Truncate => ip_address.trim().parse::<IpAddr>().map_or_else(
+                |_| String::from("<redacted>"),
+                |ip| truncate(ip).to_string(),
+            ),
+            IpAnonymization::Hash => self.hash(ip_address.trim()),
+        }
+    }
+
+    /// Anonymises every entry of a comma-separated list of IP addresses (e.g. an `X-Forwarded-For` header value).
+    #[must_use]
+    #[instrument(skip_all)]
+    pub fn anonymize_list(&self, ip_addresses: &str) -> String {
+        ip_addresses
+            .split(',')
+            .map(|ip_address| self.anonymize(ip_address))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    #[instrument(skip_all)]
+    fn hash(&self, value: &str) -> String {
+        let today: NaiveDate = Utc::now().date_naive();
+
+        let salt: [u8; 32] = {
+            let mut daily_salt = self
+                .daily_salt
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            match *daily_salt {
+                Some((date, salt)) if date == today => salt,
+                _ => {
+                    // rotate the salt at the start of every day
+                    let salt: [u8; 32] = rand::random();
+                    *daily_salt = Some((today, salt));
+                    salt
+                }
+            }
+        };
+
+        let mut hasher = Sha256::new();
+        hasher.update(salt);
+        hasher.update(value.as_bytes());
+        let hash: String = format!("{:x}", hasher.finalize());
+
+        // a short prefix is plenty to tell clients apart within a day
+        format!("ip-{}", &hash[..16])
+    }
+}
+
+impl Default for IpAnonymizer {
+    fn default() -> Self {
+        Self::new(IpAnonymization::default())
+    }
+}
+
+/// Truncates an IPv4 address to its /24 network and an IPv6 address to its /48 network.
+#[must_use]
+pub fn truncate(ip_address: IpAddr) -> IpAddr {
+    match ip_address {
+        IpAddr::V4(ipv4) => {
+            let [a, b, c, _] = ipv4.octets();
+            IpAddr::V4(Ipv4Addr::new(a, b, c, 0))
+        }
+        IpAddr::V6(ipv6) => {
+            let [a, b, c, ..] = ipv6.segments();
+            IpAddr::V6(Ipv6Addr::new(a, b, c, 0, 0, 0, 0, 0))
+        }
+    }
+}
@@ -3,13 +3,22 @@ use std::{collections::HashMap, net::SocketAddr};
 use axum::http::HeaderMap;
 use tracing::{error, info, instrument};
 
+use super::IpAnonymizer;
+
 /// Determine the client's actual IP address (not the IP address of any Proxies).
 ///
+/// The full IP address is returned, but every IP address that is logged along the way is passed through the
+/// `IpAnonymizer` first.
+///
 /// # Panics
 ///
 /// Panics if the client's actual IP address cannot be determined.
 #[instrument(skip_all)]
-pub fn resolve_true_client_ip_address(socket_addr: SocketAddr, header_map: &HeaderMap) -> String {
+pub fn resolve_true_client_ip_address(
+    socket_addr: SocketAddr,
+    header_map: &HeaderMap,
+    ip_anonymizer: &IpAnonymizer,
+) -> String {
     // prioritized list of HTTP headers that may contain the client's true IP address
     // (top-most entry is the most trusted)
     let prioritized_headers: Vec<&str> = vec![
@@ -39,7 +48,10 @@ pub fn resolve_true_client_ip_address(socket_addr: SocketAddr, header_map: &Head
                         // successfully parsed HTTP header value
                         Ok(header_value) => {
                             if *prioritized_header == "X-Forwarded-For" {
-                                info!("full HTTP 'X-Forwarded-For' header IP list: {header_value}");
+                                info!(
+                                    "full HTTP 'X-Forwarded-For' header IP list: {}",
+                                    ip_anonymizer.anonymize_list(header_value)
+                                );
 
                                 // 'X-Forwarded-For' header may contain multiple IP addresses
                                 let parts: Vec<&str> = header_value.split(',').collect();
@@ -73,15 +85,26 @@ pub fn resolve_true_client_ip_address(socket_addr: SocketAddr, header_map: &Head
             };
             (*prioritized_header, header_value)
         }).collect();
-    info!(
-        "Client IP headers: {:?}",
-        prioritized_headers_values.clone()
-    );
+    let anonymized_headers_values: HashMap<&str, Option<String>> = prioritized_headers_values
+        .iter()
+        .map(|(prioritized_header, header_value)| {
+            (
+                *prioritized_header,
+                header_value
+                    .as_ref()
+                    .map(|header_value| ip_anonymizer.anonymize(header_value)),
+            )
+        })
+        .collect();
+    info!("Client IP headers: {:?}", anonymized_headers_values);
 
     // choose the first prioritized header that exists
     for prioritized_header in prioritized_headers {
         if let Some(Some(header_value)) = prioritized_headers_values.get(prioritized_header) {
-            info!("chose HTTP '{prioritized_header}' header for true client IP: '{header_value}'");
+            info!(
+                "chose HTTP '{prioritized_header}' header for true client IP: '{}'",
+                ip_anonymizer.anonymize(header_value)
+            );
             return header_value.clone();
         }
     }
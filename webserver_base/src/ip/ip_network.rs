@@ -0,0 +1,85 @@
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// An IP address (`10.0.0.5`) or range of addresses in CIDR notation (`10.0.0.0/8`, `fd00::/8`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNetwork {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpNetwork {
+    pub const LOCALHOST_V4: Self = Self {
+        addr: IpAddr::V4(std::net::Ipv4Addr::LOCALHOST),
+        prefix_len: 32,
+    };
+
+    pub const LOCALHOST_V6: Self = Self {
+        addr: IpAddr::V6(std::net::Ipv6Addr::LOCALHOST),
+        prefix_len: 128,
+    };
+
+    /// Whether `ip` is in this network; IPv4-mapped IPv6 addresses (e.g. `::ffff:127.0.0.1`, as seen by dual-stack
+    /// listeners) match the IPv4 network.
+    #[must_use]
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                prefix_matches(&network.octets(), &ip.octets(), self.prefix_len)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                prefix_matches(&network.octets(), &ip.octets(), self.prefix_len)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn prefix_matches(network: &[u8], ip: &[u8], prefix_len: u8) -> bool {
+    let full_bytes: usize = usize::from(prefix_len / 8);
+    let remaining_bits: u8 = prefix_len % 8;
+    if network[..full_bytes] != ip[..full_bytes] {
+        return false;
+    }
+    if remaining_bits == 0 {
+        return true;
+    }
+    let mask: u8 = u8::MAX << (8 - remaining_bits);
+    network[full_bytes] & mask == ip[full_bytes] & mask
+}
+
+impl FromStr for IpNetwork {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s: &str = s.trim();
+        let (addr, prefix_len): (&str, Option<&str>) = match s.split_once('/') {
+            Some((addr, prefix_len)) => (addr, Some(prefix_len)),
+            None => (s, None),
+        };
+
+        let addr: IpAddr = addr
+            .parse()
+            .map_err(|e| format!("'{s}' is not a valid IP address or CIDR range: {e}"))?;
+        let max_prefix_len: u8 = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix_len: u8 = match prefix_len {
+            Some(prefix_len) => prefix_len
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix_len| *prefix_len <= max_prefix_len)
+                .ok_or_else(|| {
+                    format!("'{s}' has an invalid prefix length (expected 0 to {max_prefix_len})")
+                })?,
+            None => max_prefix_len,
+        };
+
+        Ok(Self { addr, prefix_len })
+    }
+}
+
+impl Display for IpNetwork {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
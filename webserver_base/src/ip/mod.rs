@@ -0,0 +1,8 @@
+mod anonymization;
+#[expect(clippy::module_inception)]
+mod ip;
+mod ip_network;
+
+pub use anonymization::*;
+pub use ip::*;
+pub use ip_network::*;
@@ -1,6 +1,14 @@
+pub mod api_router;
 pub mod axum_plausible_analytics;
 pub mod base_settings;
+pub mod build_info;
 pub mod cache_buster;
 pub mod frontend_error_logger;
+pub mod health;
 pub mod ip;
+pub mod metrics;
+pub mod request_id;
+pub mod server;
 pub mod templates;
+pub mod user_agent;
+pub mod web_server;
@@ -0,0 +1,258 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
+
+use axum::extract::{MatchedPath, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use prometheus::{
+    HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+use tracing::error;
+
+use crate::axum_plausible_analytics::{AnalyticsStats, AxumPlausibleAnalyticsHandler};
+use crate::cache_buster::CacheBuster;
+use crate::frontend_error_logger::{FrontendErrorReporter, FrontendErrorStats};
+
+/// Requests that no route matched share this `route` label, so that scanners cannot create unbounded label values.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// The route that `metrics_router` serves the metrics on.
+pub const METRICS_PATH: &str = "/metrics";
+
+type Collector = Box<dyn Fn(&MetricFamilies) + Send + Sync>;
+
+/// Prometheus metrics for the server: HTTP requests (per route and status code), cache-busted assets, analytics events
+/// and frontend errors.
+///
+/// Clones share the same metrics. Layer `Metrics::middleware` on the router to record requests, and serve them with
+/// `metrics_router`.
+#[derive(Clone)]
+pub struct Metrics {
+    families: Arc<MetricFamilies>,
+    // read the analytics and frontend error stats when scraped
+    collectors: Arc<Mutex<Vec<Collector>>>,
+}
+
+struct MetricFamilies {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    cache_buster_assets: IntGauge,
+    cache_buster_asset_bytes: IntGauge,
+    analytics_events: IntCounterVec,
+    analytics_queue_depth: IntGauge,
+    frontend_errors: IntCounterVec,
+}
+
+impl Debug for Metrics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Metrics").finish_non_exhaustive()
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    /// # Panics
+    ///
+    /// Panics if a metric definition is invalid (which would be a bug).
+    #[must_use]
+    pub fn new() -> Self {
+        let registry: Registry = Registry::new();
+        let families: MetricFamilies = MetricFamilies {
+            http_requests: register(
+                &registry,
+                IntCounterVec::new(
+                    Opts::new(
+                        "http_requests_total",
+                        "HTTP requests, by route and status code",
+                    ),
+                    &["method", "route", "status"],
+                ),
+            ),
+            http_request_duration: register(
+                &registry,
+                HistogramVec::new(
+                    HistogramOpts::new(
+                        "http_request_duration_seconds",
+                        "HTTP request latency, by route",
+                    ),
+                    &["method", "route"],
+                ),
+            ),
+            cache_buster_assets: register(
+                &registry,
+                IntGauge::new("cache_buster_assets", "Cache-busted static assets"),
+            ),
+            cache_buster_asset_bytes: register(
+                &registry,
+                IntGauge::new(
+                    "cache_buster_asset_bytes",
+                    "Combined size of the cache-busted static assets",
+                ),
+            ),
+            analytics_events: register(
+                &registry,
+                IntCounterVec::new(
+                    Opts::new(
+                        "analytics_events_total",
+                        "Analytics events, by outcome (forwarded, failed or dropped)",
+                    ),
+                    &["outcome"],
+                ),
+            ),
+            analytics_queue_depth: register(
+                &registry,
+                IntGauge::new(
+                    "analytics_queue_depth",
+                    "Analytics events waiting to be sent",
+                ),
+            ),
+            frontend_errors: register(
+                &registry,
+                IntCounterVec::new(
+                    Opts::new(
+                        "frontend_errors_total",
+                        "Frontend errors received, by outcome (reported or throttled)",
+                    ),
+                    &["outcome"],
+                ),
+            ),
+            registry,
+        };
+
+        Self {
+            families: Arc::new(families),
+            collectors: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Records the number and combined size of the cache-busted assets (which do not change after startup).
+    pub fn record_cache_buster(&self, cache_buster: &CacheBuster) {
+        self.families
+            .cache_buster_assets
+            .set(i64::try_from(cache_buster.asset_count()).unwrap_or(i64::MAX));
+        self.families
+            .cache_buster_asset_bytes
+            .set(i64::try_from(cache_buster.total_bytes()).unwrap_or(i64::MAX));
+    }
+
+    /// Reports the handler's analytics events (see `AxumPlausibleAnalyticsHandler::stats`).
+    pub fn track_analytics(&self, analytics: Arc<AxumPlausibleAnalyticsHandler>) {
+        self.add_collector(move |families| {
+            let stats: AnalyticsStats = analytics.stats();
+            sync_counter(
+                &families.analytics_events.with_label_values(&["forwarded"]),
+                stats.forwarded,
+            );
+            sync_counter(
+                &families.analytics_events.with_label_values(&["failed"]),
+                stats.failed,
+            );
+            sync_counter(
+                &families.analytics_events.with_label_values(&["dropped"]),
+                stats.dropped,
+            );
+            families
+                .analytics_queue_depth
+                .set(i64::try_from(stats.queued).unwrap_or(i64::MAX));
+        });
+    }
+
+    /// Reports the frontend errors that the reporter received (see `FrontendErrorReporter::stats`).
+    pub fn track_frontend_errors(&self, frontend_error_reporter: Arc<FrontendErrorReporter>) {
+        self.add_collector(move |families| {
+            let stats: FrontendErrorStats = frontend_error_reporter.stats();
+            sync_counter(
+                &families.frontend_errors.with_label_values(&["reported"]),
+                stats.reported,
+            );
+            sync_counter(
+                &families.frontend_errors.with_label_values(&["throttled"]),
+                stats.throttled,
+            );
+        });
+    }
+
+    fn add_collector(&self, collector: impl Fn(&MetricFamilies) + Send + Sync + 'static) {
+        self.collectors
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(collector));
+    }
+
+    /// The current metrics, in the Prometheus text exposition format.
+    #[must_use]
+    pub fn render(&self) -> String {
+        for collector in self
+            .collectors
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+        {
+            collector(&self.families);
+        }
+
+        TextEncoder::new()
+            .encode_to_string(&self.families.registry.gather())
+            .unwrap_or_else(|e| {
+                error!("failed to encode metrics: {e}");
+                String::new()
+            })
+    }
+
+    /// Records the request count and latency of every request, labelled by its route (e.g. `/blog/:slug`) rather than
+    /// its path.
+    ///
+    /// Must be added with `Router::layer`, so that the matched route is known. Scrapes of `/metrics` itself are not
+    /// recorded.
+    pub async fn middleware(State(metrics): State<Self>, req: Request, next: Next) -> Response {
+        let method: String = req.method().to_string();
+        let route: String = req.extensions().get::<MatchedPath>().map_or_else(
+            || UNMATCHED_ROUTE.to_string(),
+            |route| route.as_str().to_string(),
+        );
+        if route == METRICS_PATH {
+            return next.run(req).await;
+        }
+        let start: Instant = Instant::now();
+
+        let response: Response = next.run(req).await;
+
+        metrics
+            .families
+            .http_request_duration
+            .with_label_values(&[method.as_str(), route.as_str()])
+            .observe(start.elapsed().as_secs_f64());
+        metrics
+            .families
+            .http_requests
+            .with_label_values(&[method.as_str(), route.as_str(), response.status().as_str()])
+            .inc();
+        response
+    }
+}
+
+fn register<M>(registry: &Registry, metric: prometheus::Result<M>) -> M
+where
+    M: prometheus::core::Collector + Clone + 'static,
+{
+    let metric: M = metric.expect("invalid metric definition");
+    registry
+        .register(Box::new(metric.clone()))
+        .expect("duplicate metric definition");
+    metric
+}
+
+/// Advances a Prometheus counter to a total that is counted elsewhere.
+fn sync_counter(counter: &IntCounter, total: u64) {
+    let current: u64 = counter.get();
+    if total > current {
+        counter.inc_by(total - current);
+    }
+}
@@ -0,0 +1,87 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::Router;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header::CONTENT_TYPE};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use tracing::{instrument, warn};
+
+use super::{METRICS_PATH, Metrics};
+use crate::ip::IpNetwork;
+use crate::server::UNIX_SOCKET_PEER_ADDR;
+
+/// Headers that reverse proxies add; a request carrying them came from outside, whatever its peer address.
+const FORWARDING_HEADERS: [&str; 3] = ["forwarded", "x-forwarded-for", "x-real-ip"];
+
+/// Who may scrape `GET /metrics`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetricsAccess {
+    /// Anyone who can connect, for routers served on an internal-only listener.
+    Internal,
+
+    /// Clients connecting directly (not through a reverse proxy) over TCP from these networks.
+    Allowlist(Vec<IpNetwork>),
+}
+
+/// Serves `GET /metrics` in the Prometheus text format.
+///
+/// With `MetricsAccess::Allowlist`, the router must be served with `ConnectInfo<SocketAddr>`.
+pub fn metrics_router(metrics: Metrics, access: MetricsAccess) -> Router {
+    let router: Router = Router::new()
+        .route(METRICS_PATH, get(render_metrics))
+        .with_state(metrics);
+
+    match access {
+        MetricsAccess::Internal => router,
+        MetricsAccess::Allowlist(allowlist) => router.layer(axum::middleware::from_fn_with_state(
+            Arc::new(allowlist),
+            allowlist_middleware,
+        )),
+    }
+}
+
+#[instrument(skip_all)]
+async fn render_metrics(State(metrics): State<Metrics>) -> Response {
+    (
+        [(
+            CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; version=0.0.4; charset=utf-8"),
+        )],
+        metrics.render(),
+    )
+        .into_response()
+}
+
+async fn allowlist_middleware(
+    State(allowlist): State<Arc<Vec<IpNetwork>>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    req: Request,
+    next: Next,
+) -> Response {
+    // Unix socket peers have no address of their own, and are usually a local reverse proxy
+    let refusal: Option<&str> = if addr == UNIX_SOCKET_PEER_ADDR {
+        Some("the client connected over a Unix socket")
+    } else if is_proxied(req.headers()) {
+        Some("the request was forwarded by a reverse proxy")
+    } else if !allowlist.iter().any(|network| network.contains(addr.ip())) {
+        Some("the client is not on the allowlist")
+    } else {
+        None
+    };
+
+    // the client's IP address is left out, as it is not anonymised here
+    if let Some(refusal) = refusal {
+        warn!("refused to serve metrics: {refusal}");
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    next.run(req).await
+}
+
+fn is_proxied(headers: &HeaderMap) -> bool {
+    FORWARDING_HEADERS
+        .iter()
+        .any(|header| headers.contains_key(*header))
+}
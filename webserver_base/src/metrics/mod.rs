@@ -0,0 +1,6 @@
+#[expect(clippy::module_inception)]
+mod metrics;
+mod metrics_router;
+
+pub use metrics::*;
+pub use metrics_router::*;
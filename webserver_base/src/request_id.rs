@@ -0,0 +1,123 @@
+use std::fmt::{Display, Formatter};
+
+use axum::extract::Request;
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+use tower_http::trace::MakeSpan;
+use tracing::{Level, Span};
+
+/// Correlates the log lines, Sentry events and response of one request, and is forwarded by reverse proxies.
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longer incoming IDs are replaced, so clients cannot bloat every log line.
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// The ID of the current request, available to handlers as `Extension<RequestId>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(String);
+
+impl RequestId {
+    /// A random 128-bit ID (32 hex characters).
+    #[must_use]
+    pub fn generate() -> Self {
+        Self(format!("{:032x}", rand::random::<u128>()))
+    }
+
+    /// The request's `X-Request-Id` header (e.g. set by a reverse proxy), unless it is empty, too long, or contains
+    /// anything but visible ASCII characters.
+    #[must_use]
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|request_id| request_id.to_str().ok())
+            .filter(|request_id| {
+                !request_id.is_empty()
+                    && request_id.len() <= MAX_REQUEST_ID_LENGTH
+                    && request_id.chars().all(|c| c.is_ascii_graphic())
+            })
+            .map(|request_id| Self(request_id.to_string()))
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for RequestId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Reads the request's `X-Request-Id` or generates one, then makes it available to everything that handles the
+/// request: the `RequestId` extension, the request's `X-Request-Id` header, and the Sentry scope (as the `request_id`
+/// tag). The response echoes it back.
+///
+/// Layer it outside of `TraceLayer` (so `RequestIdMakeSpan` can record it) but inside of `NewSentryLayer` (so the tag
+/// is set on the request's own Sentry hub).
+pub async fn request_id_middleware(mut req: Request, next: Next) -> Response {
+    let request_id: RequestId =
+        RequestId::from_headers(req.headers()).unwrap_or_else(RequestId::generate);
+    // always valid, since request IDs only contain visible ASCII characters
+    let header_value: Option<HeaderValue> = HeaderValue::from_str(request_id.as_str()).ok();
+
+    if let Some(header_value) = &header_value {
+        req.headers_mut()
+            .insert(REQUEST_ID_HEADER, header_value.clone());
+    }
+    sentry::configure_scope(|scope| scope.set_tag("request_id", &request_id));
+    req.extensions_mut().insert(request_id);
+
+    let mut response: Response = next.run(req).await;
+    if let Some(header_value) = header_value {
+        response
+            .headers_mut()
+            .insert(REQUEST_ID_HEADER, header_value);
+    }
+    response
+}
+
+/// Creates `TraceLayer` spans that record the request ID (see `request_id_middleware`), so every log line of a request
+/// can be correlated.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestIdMakeSpan {
+    level: Level,
+}
+
+impl RequestIdMakeSpan {
+    #[must_use]
+    pub const fn new(level: Level) -> Self {
+        Self { level }
+    }
+}
+
+impl<B> MakeSpan<B> for RequestIdMakeSpan {
+    fn make_span(&mut self, request: &axum::http::Request<B>) -> Span {
+        // `tracing` needs the level to be known at compile time
+        macro_rules! request_span {
+            ($level:expr) => {
+                tracing::span!(
+                    $level,
+                    "request",
+                    method = %request.method(),
+                    uri = %request.uri(),
+                    version = ?request.version(),
+                    request_id = request
+                        .extensions()
+                        .get::<RequestId>()
+                        .map(RequestId::as_str),
+                )
+            };
+        }
+
+        match self.level {
+            Level::ERROR => request_span!(Level::ERROR),
+            Level::WARN => request_span!(Level::WARN),
+            Level::INFO => request_span!(Level::INFO),
+            Level::DEBUG => request_span!(Level::DEBUG),
+            Level::TRACE => request_span!(Level::TRACE),
+        }
+    }
+}
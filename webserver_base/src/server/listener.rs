@@ -0,0 +1,144 @@
+use std::fmt::{Debug, Formatter};
+#[cfg(unix)]
+use std::fs::{self, DirBuilder, Permissions};
+use std::io;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use tokio_rustls::TlsAcceptor;
+use tracing::{info, instrument};
+
+use super::{ListenerConfig, TlsConfig};
+
+/// A bound listener, ready to be served.
+pub enum Listener {
+    Tcp(TcpListener),
+    Https(TcpListener, TlsAcceptor),
+    HttpsRedirect(TcpListener, u16),
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf),
+}
+
+impl Debug for Listener {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // `TlsAcceptor` does not implement `Debug`
+        match self {
+            Self::Tcp(listener) => f.debug_tuple("Tcp").field(listener).finish(),
+            Self::Https(listener, _) => f
+                .debug_tuple("Https")
+                .field(listener)
+                .finish_non_exhaustive(),
+            Self::HttpsRedirect(listener, https_port) => f
+                .debug_tuple("HttpsRedirect")
+                .field(listener)
+                .field(https_port)
+                .finish(),
+            #[cfg(unix)]
+            Self::Unix(listener, path) => {
+                f.debug_tuple("Unix").field(listener).field(path).finish()
+            }
+        }
+    }
+}
+
+impl Listener {
+    /// Binds the listener.
+    ///
+    /// TCP listeners on the IPv6 unspecified address (`[::]`) are dual-stack. A stale Unix socket file (e.g. left over
+    /// from a crash) is replaced, but any other file at the socket path is left alone.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the address cannot be bound, a file that is not a socket exists at the socket path, the
+    /// socket file's permissions cannot be set, or an HTTPS listener has no (valid) `tls` certificate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an HTTPS listener is bound outside of a Tokio runtime.
+    #[instrument(skip_all)]
+    pub fn bind(config: &ListenerConfig, tls: Option<&TlsConfig>) -> io::Result<Self> {
+        let listener: Self = match config {
+            ListenerConfig::Tcp(addr) => Self::Tcp(bind_tcp(*addr)?),
+            ListenerConfig::Https(addr) => {
+                let Some(tls) = tls else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("{config} requires a TLS certificate and key"),
+                    ));
+                };
+                Self::Https(bind_tcp(*addr)?, tls.acceptor()?)
+            }
+            ListenerConfig::HttpsRedirect { addr, https_port } => {
+                Self::HttpsRedirect(bind_tcp(*addr)?, *https_port)
+            }
+            #[cfg(unix)]
+            ListenerConfig::Unix { path, mode } => {
+                Self::Unix(bind_unix(path, *mode)?, path.clone())
+            }
+        };
+        info!("listening on {config}");
+        Ok(listener)
+    }
+}
+
+fn bind_tcp(addr: SocketAddr) -> io::Result<TcpListener> {
+    let socket: Socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    if addr.is_ipv6() && addr.ip().is_unspecified() {
+        socket.set_only_v6(false)?;
+    }
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&addr.into())?;
+    socket.listen(1024)?;
+    TcpListener::from_std(socket.into())
+}
+
+#[cfg(unix)]
+fn bind_unix(path: &Path, mode: Option<u32>) -> io::Result<UnixListener> {
+    // a mistyped path must not delete whatever file is there
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("'{}' exists and is not a socket", path.display()),
+            ));
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+
+    let Some(mode) = mode else {
+        return UnixListener::bind(path);
+    };
+
+    // bound in a private directory and moved into place, so that no other process can connect before the mode is set
+    let Some(file_name) = path.file_name() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{}' is not a socket file path", path.display()),
+        ));
+    };
+    let private_dir: PathBuf = path.with_file_name(format!(
+        ".{}.{}",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+    DirBuilder::new().mode(0o700).create(&private_dir)?;
+    let private_path: PathBuf = private_dir.join(file_name);
+    let listener: io::Result<UnixListener> =
+        UnixListener::bind(&private_path).and_then(|listener| {
+            fs::set_permissions(&private_path, Permissions::from_mode(mode))?;
+            fs::rename(&private_path, path)?;
+            Ok(listener)
+        });
+    fs::remove_dir_all(&private_dir)?;
+    listener
+}
@@ -0,0 +1,117 @@
+use std::fmt::{Display, Formatter};
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// An address the server accepts connections on.
+///
+/// Parsed from:
+///
+/// - `127.0.0.1:8080`, `[::]:8080` or `tcp://0.0.0.0:8080` (binding `[::]` accepts both IPv6 and IPv4 connections)
+/// - `https://[::]:443` (requires the TLS certificate and key settings)
+/// - `redirect://[::]:80`, which redirects every request to HTTPS on port 443, or another port:
+///   `redirect://[::]:8080?https_port=8443`
+/// - `unix:/run/site/site.sock`, optionally with permissions (octal) for the socket file:
+///   `unix:/run/site/site.sock?mode=660` (Unix only)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenerConfig {
+    Tcp(SocketAddr),
+    Https(SocketAddr),
+    HttpsRedirect {
+        addr: SocketAddr,
+        https_port: u16,
+    },
+    #[cfg(unix)]
+    Unix {
+        path: PathBuf,
+        mode: Option<u32>,
+    },
+}
+
+impl FromStr for ListenerConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s: &str = s.trim();
+
+        if let Some(unix) = s.strip_prefix("unix:") {
+            return parse_unix(s, unix);
+        }
+
+        if let Some(https) = s.strip_prefix("https://") {
+            return parse_socket_addr(https).map(Self::Https);
+        }
+
+        if let Some(redirect) = s.strip_prefix("redirect://") {
+            let (addr, https_port): (&str, u16) = match redirect.split_once("?https_port=") {
+                Some((addr, https_port)) => (
+                    addr,
+                    https_port
+                        .parse()
+                        .map_err(|e| format!("'{https_port}' is not a valid HTTPS port: {e}"))?,
+                ),
+                None => (redirect, 443),
+            };
+            return Ok(Self::HttpsRedirect {
+                addr: parse_socket_addr(addr)?,
+                https_port,
+            });
+        }
+
+        parse_socket_addr(s.strip_prefix("tcp://").unwrap_or(s)).map(Self::Tcp)
+    }
+}
+
+#[cfg(unix)]
+fn parse_unix(s: &str, unix: &str) -> Result<ListenerConfig, String> {
+    let (path, mode): (&str, Option<u32>) =
+        match unix.split_once("?mode=") {
+            Some((path, mode)) => (
+                path,
+                Some(u32::from_str_radix(mode, 8).map_err(|e| {
+                    format!("'{mode}' is not a valid (octal) socket file mode: {e}")
+                })?),
+            ),
+            None => (unix, None),
+        };
+    if path.is_empty() {
+        return Err(format!("'{s}' is missing the socket path"));
+    }
+    Ok(ListenerConfig::Unix {
+        path: PathBuf::from(path),
+        mode,
+    })
+}
+
+#[cfg(not(unix))]
+fn parse_unix(s: &str, _unix: &str) -> Result<ListenerConfig, String> {
+    Err(format!(
+        "'{s}': Unix sockets are not supported on this platform"
+    ))
+}
+
+fn parse_socket_addr(s: &str) -> Result<SocketAddr, String> {
+    s.parse::<SocketAddr>().map_err(|e| {
+        format!("'{s}' is not a valid listener (expected e.g. `0.0.0.0:8080`, `[::]:8080` or `unix:/path/to.sock`): {e}")
+    })
+}
+
+impl Display for ListenerConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "tcp://{addr}"),
+            Self::Https(addr) => write!(f, "https://{addr}"),
+            Self::HttpsRedirect { addr, https_port } => {
+                write!(f, "redirect://{addr}?https_port={https_port}")
+            }
+            #[cfg(unix)]
+            Self::Unix { path, mode: None } => write!(f, "unix:{}", path.display()),
+            #[cfg(unix)]
+            Self::Unix {
+                path,
+                mode: Some(mode),
+            } => write!(f, "unix:{}?mode={mode:o}", path.display()),
+        }
+    }
+}
@@ -0,0 +1,13 @@
+mod listener;
+mod listener_config;
+mod readiness;
+mod serve;
+mod server_runner;
+mod tls;
+
+pub use listener::*;
+pub use listener_config::*;
+pub use readiness::*;
+pub use serve::*;
+pub use server_runner::*;
+pub use tls::*;
@@ -0,0 +1,431 @@
+#[cfg(unix)]
+use std::fs;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::pin::pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::Router;
+use axum::extract::{ConnectInfo, Request};
+use axum::http::{HeaderMap, StatusCode, Uri, header::HOST};
+use axum::response::{IntoResponse, Redirect, Response};
+use hyper::body::Incoming;
+use hyper::rt::Executor;
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use hyper_util::server::conn::auto;
+use hyper_util::server::graceful::{GracefulShutdown, Watcher};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+use tokio_rustls::TlsAcceptor;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+use tower::Service;
+use tracing::{debug, error, info, instrument, warn};
+
+use super::Listener;
+
+/// Unix socket peers have no IP address, so handlers see this one; the reverse proxy's forwarding headers (e.g.
+/// `X-Forwarded-For`) identify the actual client.
+pub const UNIX_SOCKET_PEER_ADDR: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
+
+/// Clients that do not finish the TLS handshake within this time are disconnected.
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Serves `app` on every listener until `shutdown` completes, then waits for in-flight requests to finish.
+///
+/// Handlers can extract `ConnectInfo<SocketAddr>` on every listener (see `UNIX_SOCKET_PEER_ADDR`). If one listener
+/// fails, the others are shut down too.
+///
+/// # Errors
+///
+/// Will return the first error that any listener failed with.
+#[instrument(skip_all)]
+pub async fn serve_listeners<F>(
+    listeners: Vec<Listener>,
+    app: Router,
+    shutdown: F,
+) -> io::Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    serve_apps(
+        listeners
+            .into_iter()
+            .map(|listener| (listener, app.clone()))
+            .collect(),
+        shutdown,
+    )
+    .await
+}
+
+/// Like `serve_listeners`, but with a different app per listener (e.g. metrics on an internal listener).
+///
+/// # Errors
+///
+/// Will return the first error that any listener failed with.
+#[instrument(skip_all)]
+pub async fn serve_apps<F>(apps: Vec<(Listener, Router)>, shutdown: F) -> io::Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    serve_apps_draining(apps, shutdown, None).await
+}
+
+/// Like `serve_apps`, but once `drain_timeout` has passed after `shutdown`, the connections that are still open are
+/// closed (cancelling their in-flight requests).
+pub(super) async fn serve_apps_draining<F>(
+    apps: Vec<(Listener, Router)>,
+    shutdown: F,
+    drain_timeout: Option<Duration>,
+) -> io::Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let (shutdown_sender, shutdown_receiver) = watch::channel(false);
+    let shutdown_sender: Arc<watch::Sender<bool>> = Arc::new(shutdown_sender);
+
+    let mut servers: JoinSet<io::Result<()>> = JoinSet::new();
+    for (listener, app) in apps {
+        let mut shutdown_receiver: watch::Receiver<bool> = shutdown_receiver.clone();
+        let shutdown_signal = async move {
+            // an error means the sender is gone, which also means shutting down
+            let _ = shutdown_receiver
+                .wait_for(|shutting_down| *shutting_down)
+                .await;
+        };
+
+        match listener {
+            Listener::Tcp(listener) => {
+                servers.spawn(serve_tcp(listener, app, shutdown_signal, drain_timeout))
+            }
+            Listener::Https(listener, acceptor) => servers.spawn(serve_https(
+                listener,
+                acceptor,
+                app,
+                shutdown_signal,
+                drain_timeout,
+            )),
+            Listener::HttpsRedirect(listener, https_port) => servers.spawn(serve_tcp(
+                listener,
+                https_redirect_router(https_port),
+                shutdown_signal,
+                drain_timeout,
+            )),
+            #[cfg(unix)]
+            Listener::Unix(listener, path) => servers.spawn(serve_unix(
+                listener,
+                path,
+                app,
+                shutdown_signal,
+                drain_timeout,
+            )),
+        };
+    }
+
+    let external_shutdown_sender: Arc<watch::Sender<bool>> = Arc::clone(&shutdown_sender);
+    tokio::spawn(async move {
+        shutdown.await;
+        info!("shutting down");
+        external_shutdown_sender.send_replace(true);
+    });
+
+    let mut result: io::Result<()> = Ok(());
+    while let Some(server_result) = servers.join_next().await {
+        if let Err(e) = server_result.map_err(io::Error::other).and_then(|r| r) {
+            error!("listener failed: {e}");
+            shutdown_sender.send_replace(true);
+            if result.is_ok() {
+                result = Err(e);
+            }
+        }
+    }
+    result
+}
+
+/// The tasks serving a listener's connections (including their HTTP/2 streams), so that shutting down can wait for
+/// them to finish, and cancel them once the drain timeout has passed.
+#[derive(Clone)]
+struct Connections {
+    tasks: TaskTracker,
+    cancel: CancellationToken,
+}
+
+impl<F> Executor<F> for Connections
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    fn execute(&self, future: F) {
+        self.spawn(async move {
+            future.await;
+        });
+    }
+}
+
+impl Connections {
+    fn new() -> Self {
+        Self {
+            tasks: TaskTracker::new(),
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    fn spawn<F>(&self, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let cancel: CancellationToken = self.cancel.clone();
+        self.tasks.spawn(async move {
+            tokio::select! {
+                () = future => {}
+                () = cancel.cancelled() => {}
+            }
+        });
+    }
+
+    /// Asks the connections to close once their in-flight requests finish, and waits for them; connections that are
+    /// still open after `drain_timeout` are closed without waiting.
+    async fn drain(self, graceful: GracefulShutdown, drain_timeout: Option<Duration>) {
+        self.tasks.close();
+        let drained = async {
+            graceful.shutdown().await;
+            self.tasks.wait().await;
+        };
+
+        match drain_timeout {
+            None => drained.await,
+            Some(drain_timeout) => {
+                if tokio::time::timeout(drain_timeout, drained).await.is_err() {
+                    warn!(
+                        "{} connections did not finish within {drain_timeout:?}; closing them",
+                        self.tasks.len()
+                    );
+                    self.cancel.cancel();
+                    self.tasks.wait().await;
+                }
+            }
+        }
+    }
+}
+
+/// A bound listener that `accept_loop` takes connections from.
+trait Accept: Send + 'static {
+    type Io: AsyncRead + AsyncWrite + Unpin + Send + 'static;
+
+    /// The next connection, and the peer address that handlers see as `ConnectInfo<SocketAddr>`.
+    fn accept(&self) -> impl Future<Output = io::Result<(Self::Io, SocketAddr)>> + Send;
+}
+
+impl Accept for TcpListener {
+    type Io = TcpStream;
+
+    async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        Self::accept(self).await
+    }
+}
+
+#[cfg(unix)]
+impl Accept for UnixListener {
+    type Io = UnixStream;
+
+    async fn accept(&self) -> io::Result<(UnixStream, SocketAddr)> {
+        let (stream, _) = Self::accept(self).await?;
+        Ok((stream, UNIX_SOCKET_PEER_ADDR))
+    }
+}
+
+/// Accepts connections until `shutdown` completes and hands each one to `serve` (in its own task), then closes the
+/// listener and drains the connections.
+///
+/// `axum::serve` supports neither TLS nor Unix sockets, nor closing connections that outlive the drain timeout, so
+/// every listener is served with hyper directly.
+async fn accept_loop<L, F, S, C>(
+    listener: L,
+    shutdown: F,
+    drain_timeout: Option<Duration>,
+    serve: S,
+) where
+    L: Accept,
+    F: Future<Output = ()> + Send + 'static,
+    S: Fn(L::Io, SocketAddr, Watcher, Connections) -> C,
+    C: Future<Output = ()> + Send + 'static,
+{
+    let connections: Connections = Connections::new();
+    let graceful: GracefulShutdown = GracefulShutdown::new();
+    let mut shutdown = pin!(shutdown);
+
+    loop {
+        let (io, peer_addr) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    // e.g. too many open files; back off instead of spinning
+                    error!("failed to accept connection: {e}");
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            },
+            () = &mut shutdown => break,
+        };
+
+        connections.spawn(serve(
+            io,
+            peer_addr,
+            graceful.watcher(),
+            connections.clone(),
+        ));
+    }
+
+    drop(listener);
+    connections.drain(graceful, drain_timeout).await;
+}
+
+#[instrument(skip_all)]
+async fn serve_tcp<F>(
+    listener: TcpListener,
+    app: Router,
+    shutdown: F,
+    drain_timeout: Option<Duration>,
+) -> io::Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    accept_loop(
+        listener,
+        shutdown,
+        drain_timeout,
+        |stream, peer_addr, watcher, executor| {
+            serve_connection(stream, peer_addr, app.clone(), watcher, executor)
+        },
+    )
+    .await;
+    Ok(())
+}
+
+#[instrument(skip_all)]
+async fn serve_https<F>(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    app: Router,
+    shutdown: F,
+    drain_timeout: Option<Duration>,
+) -> io::Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    accept_loop(
+        listener,
+        shutdown,
+        drain_timeout,
+        |stream, peer_addr, watcher, executor| {
+            let acceptor: TlsAcceptor = acceptor.clone();
+            let app: Router = app.clone();
+            async move {
+                match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(tls_stream)) => {
+                        serve_connection(tls_stream, peer_addr, app, watcher, executor).await;
+                    }
+                    Ok(Err(e)) => debug!("TLS handshake failed: {e}"),
+                    Err(_) => debug!("TLS handshake timed out"),
+                }
+            }
+        },
+    )
+    .await;
+    Ok(())
+}
+
+/// Removes the socket file once the listener is closed.
+#[cfg(unix)]
+#[instrument(skip_all)]
+async fn serve_unix<F>(
+    listener: UnixListener,
+    path: PathBuf,
+    app: Router,
+    shutdown: F,
+    drain_timeout: Option<Duration>,
+) -> io::Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    accept_loop(
+        listener,
+        shutdown,
+        drain_timeout,
+        |stream, peer_addr, watcher, executor| {
+            serve_connection(stream, peer_addr, app.clone(), watcher, executor)
+        },
+    )
+    .await;
+    fs::remove_file(&path)
+}
+
+/// Serves one HTTP/1.1 or HTTP/2 connection, making `peer_addr` available to handlers as `ConnectInfo<SocketAddr>`.
+async fn serve_connection<I>(
+    io: I,
+    peer_addr: SocketAddr,
+    app: Router,
+    watcher: Watcher,
+    executor: Connections,
+) where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let service = service_fn(move |mut request: Request<Incoming>| {
+        request.extensions_mut().insert(ConnectInfo(peer_addr));
+        app.clone().call(request)
+    });
+    let connection = watcher.watch(
+        auto::Builder::new(executor)
+            .serve_connection(TokioIo::new(io), service)
+            .into_owned(),
+    );
+    if let Err(e) = connection.await {
+        debug!("connection closed with an error: {e}");
+    }
+}
+
+/// Permanently redirects every request to the same host and path over HTTPS.
+fn https_redirect_router(https_port: u16) -> Router {
+    Router::new().fallback(move |headers: HeaderMap, uri: Uri| async move {
+        https_redirect(&headers, &uri, https_port)
+    })
+}
+
+fn https_redirect(headers: &HeaderMap, uri: &Uri, https_port: u16) -> Response {
+    let Some(host) = headers
+        .get(HOST)
+        .and_then(|host| host.to_str().ok())
+        .map(host_without_port)
+        .filter(|host| !host.is_empty())
+    else {
+        return (StatusCode::BAD_REQUEST, "missing Host header").into_response();
+    };
+
+    let port: String = if https_port == 443 {
+        String::new()
+    } else {
+        format!(":{https_port}")
+    };
+    let path_and_query: &str = uri
+        .path_and_query()
+        .map_or("/", |path_and_query| path_and_query.as_str());
+    Redirect::permanent(&format!("https://{host}{port}{path_and_query}")).into_response()
+}
+
+/// `example.com:80` -> `example.com`, `[::1]:80` -> `[::1]`
+fn host_without_port(host: &str) -> &str {
+    if host.starts_with('[') {
+        host.find(']').map_or(host, |end| &host[..=end])
+    } else {
+        host.split(':').next().unwrap_or(host)
+    }
+}
@@ -0,0 +1,205 @@
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::time::Duration;
+
+use axum::Router;
+#[cfg(unix)]
+use tokio::signal::unix::{SignalKind, signal};
+use tracing::{info, instrument, warn};
+
+use super::serve::serve_apps_draining;
+use super::{Listener, Readiness};
+
+type ShutdownSignal = Pin<Box<dyn Future<Output = ()> + Send>>;
+type ShutdownHook = Box<dyn FnOnce() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send>;
+
+/// Serves an app until SIGTERM or SIGINT, then shuts down gracefully:
+///
+/// 1. readiness is reported as not ready, and the listeners stop accepting connections
+/// 2. in-flight requests are given `drain_timeout` to finish, after which their connections are closed
+/// 3. the shutdown hooks (e.g. flushing analytics events or Sentry) run in the order they were registered, each given
+///    `hook_timeout`
+///
+/// The defaults fit within the 10 seconds that Docker waits between SIGTERM and SIGKILL.
+pub struct ServerRunner {
+    listeners: Vec<Listener>,
+    app: Router,
+    additional_listeners: Vec<(Listener, Router)>,
+    readiness: Readiness,
+    shutdown_signal: ShutdownSignal,
+    drain_timeout: Duration,
+    hook_timeout: Duration,
+    shutdown_hooks: Vec<(String, ShutdownHook)>,
+}
+
+impl Debug for ServerRunner {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServerRunner")
+            .field("listeners", &self.listeners)
+            .field(
+                "additional_listeners",
+                &self
+                    .additional_listeners
+                    .iter()
+                    .map(|(listener, _)| listener)
+                    .collect::<Vec<&Listener>>(),
+            )
+            .field("readiness", &self.readiness)
+            .field("drain_timeout", &self.drain_timeout)
+            .field("hook_timeout", &self.hook_timeout)
+            .field(
+                "shutdown_hooks",
+                &self
+                    .shutdown_hooks
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<&String>>(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl ServerRunner {
+    #[must_use]
+    pub fn new(listeners: Vec<Listener>, app: Router) -> Self {
+        Self {
+            listeners,
+            app,
+            additional_listeners: Vec::new(),
+            readiness: Readiness::new(),
+            shutdown_signal: Box::pin(shutdown_signal()),
+            drain_timeout: Duration::from_secs(5),
+            hook_timeout: Duration::from_secs(2),
+            shutdown_hooks: Vec::new(),
+        }
+    }
+
+    /// Serves a different app on another listener, e.g. metrics on an internal-only address.
+    #[must_use]
+    pub fn additional_listener(mut self, listener: Listener, app: Router) -> Self {
+        self.additional_listeners.push((listener, app));
+        self
+    }
+
+    /// The readiness flag that the app's health endpoint reports (see `HasReadiness`).
+    #[must_use]
+    pub fn readiness(mut self, readiness: Readiness) -> Self {
+        self.readiness = readiness;
+        self
+    }
+
+    /// Shuts down when `shutdown_signal` completes, instead of on SIGTERM or SIGINT.
+    #[must_use]
+    pub fn shutdown_signal<F>(mut self, shutdown_signal: F) -> Self
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.shutdown_signal = Box::pin(shutdown_signal);
+        self
+    }
+
+    /// How long in-flight requests may take to finish once shutting down; their connections are closed afterwards.
+    #[must_use]
+    pub const fn drain_timeout(mut self, drain_timeout: Duration) -> Self {
+        self.drain_timeout = drain_timeout;
+        self
+    }
+
+    /// How long each shutdown hook may take before it is abandoned.
+    #[must_use]
+    pub const fn hook_timeout(mut self, hook_timeout: Duration) -> Self {
+        self.hook_timeout = hook_timeout;
+        self
+    }
+
+    /// Registers a hook to run after the in-flight requests have drained.
+    #[must_use]
+    pub fn on_shutdown<F, Fut>(mut self, name: impl Into<String>, hook: F) -> Self
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.shutdown_hooks
+            .push((name.into(), Box::new(move || Box::pin(hook()))));
+        self
+    }
+
+    /// Serves the app until the shutdown signal, then drains in-flight requests and runs the shutdown hooks.
+    ///
+    /// # Errors
+    ///
+    /// Will return the first error that any listener failed with (the shutdown hooks still run).
+    #[instrument(skip_all)]
+    pub async fn run(self) -> io::Result<()> {
+        let shutdown = {
+            let readiness: Readiness = self.readiness.clone();
+            let shutdown_signal: ShutdownSignal = self.shutdown_signal;
+            let drain_timeout: Duration = self.drain_timeout;
+            async move {
+                shutdown_signal.await;
+                readiness.set_ready(false);
+                info!("draining in-flight requests (for up to {drain_timeout:?})");
+            }
+        };
+
+        self.readiness.set_ready(true);
+        let mut apps: Vec<(Listener, Router)> = self
+            .listeners
+            .into_iter()
+            .map(|listener| (listener, self.app.clone()))
+            .collect();
+        apps.extend(self.additional_listeners);
+        // only returns once every connection is closed, so the hooks never run alongside requests
+        let result: io::Result<()> =
+            serve_apps_draining(apps, shutdown, Some(self.drain_timeout)).await;
+        self.readiness.set_ready(false);
+
+        for (name, hook) in self.shutdown_hooks {
+            info!("running shutdown hook '{name}'");
+            if tokio::time::timeout(self.hook_timeout, hook())
+                .await
+                .is_err()
+            {
+                warn!(
+                    "shutdown hook '{name}' did not finish within {:?}",
+                    self.hook_timeout
+                );
+            }
+        }
+        info!("shutdown complete");
+
+        result
+    }
+}
+
+/// Completes when the process receives SIGTERM (e.g. `docker stop`) or SIGINT (Ctrl+C).
+///
+/// # Panics
+///
+/// Panics if the signal handlers cannot be installed.
+#[cfg(unix)]
+#[instrument(skip_all)]
+pub async fn shutdown_signal() {
+    let mut sigterm = signal(SignalKind::terminate()).expect("failed to install SIGTERM handler");
+    let mut sigint = signal(SignalKind::interrupt()).expect("failed to install SIGINT handler");
+    tokio::select! {
+        _ = sigterm.recv() => info!("received SIGTERM"),
+        _ = sigint.recv() => info!("received SIGINT"),
+    }
+}
+
+/// Completes when the process receives Ctrl+C.
+///
+/// # Panics
+///
+/// Panics if the signal handler cannot be installed.
+#[cfg(not(unix))]
+#[instrument(skip_all)]
+pub async fn shutdown_signal() {
+    tokio::signal::ctrl_c()
+        .await
+        .expect("failed to install Ctrl+C handler");
+    info!("received Ctrl+C");
+}
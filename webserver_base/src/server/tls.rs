@@ -0,0 +1,195 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, PoisonError, RwLock, Weak};
+use std::time::{Duration, SystemTime};
+
+use rustls::ServerConfig;
+use rustls::crypto::CryptoProvider;
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::TlsAcceptor;
+use tracing::{error, info, instrument};
+
+/// Certificate chain and private key (both PEM) for HTTPS listeners.
+///
+/// The files are checked for changes every `reload_interval`, so renewed certificates (e.g. from certbot) are picked
+/// up without a restart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+    pub reload_interval: Duration,
+}
+
+impl TlsConfig {
+    #[must_use]
+    pub fn new(cert_path: impl Into<PathBuf>, key_path: impl Into<PathBuf>) -> Self {
+        Self {
+            cert_path: cert_path.into(),
+            key_path: key_path.into(),
+            reload_interval: Duration::from_secs(10),
+        }
+    }
+
+    /// Builds a TLS acceptor that negotiates HTTP/2 or HTTP/1.1 (ALPN), and starts watching the certificate files.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the certificate or private key cannot be read or parsed, or if they do not match.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    #[instrument(skip_all)]
+    pub fn acceptor(&self) -> io::Result<TlsAcceptor> {
+        let provider: Arc<CryptoProvider> = Arc::new(rustls::crypto::ring::default_provider());
+        let resolver: Arc<ReloadingCertResolver> = Arc::new(ReloadingCertResolver::new(
+            self.clone(),
+            Arc::clone(&provider),
+        )?);
+
+        let mut server_config: ServerConfig = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(io::Error::other)?
+            .with_no_client_auth()
+            .with_cert_resolver(Arc::clone(&resolver) as Arc<dyn ResolvesServerCert>);
+        server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+        tokio::spawn(watch_cert_files(
+            Arc::downgrade(&resolver),
+            self.reload_interval,
+        ));
+
+        Ok(TlsAcceptor::from(Arc::new(server_config)))
+    }
+}
+
+/// Serves the most recently loaded certificate to every client.
+#[derive(Debug)]
+struct ReloadingCertResolver {
+    tls_config: TlsConfig,
+    provider: Arc<CryptoProvider>,
+    // the certificate, and when its files were last modified
+    current: RwLock<(Arc<CertifiedKey>, Option<SystemTime>)>,
+}
+
+impl ReloadingCertResolver {
+    fn new(tls_config: TlsConfig, provider: Arc<CryptoProvider>) -> io::Result<Self> {
+        let modified: Option<SystemTime> = last_modified(&tls_config);
+        let certified_key: CertifiedKey = load_certified_key(&tls_config, &provider)?;
+        Ok(Self {
+            tls_config,
+            provider,
+            current: RwLock::new((Arc::new(certified_key), modified)),
+        })
+    }
+
+    /// Reloads the certificate if its files changed; a broken certificate, or one that does not match its private key,
+    /// is logged and the previous one kept.
+    fn reload_if_changed(&self) {
+        let modified: Option<SystemTime> = last_modified(&self.tls_config);
+        let loaded_modified: Option<SystemTime> = self
+            .current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .1;
+        if modified.is_none() || modified == loaded_modified {
+            return;
+        }
+
+        match load_certified_key(&self.tls_config, &self.provider) {
+            Ok(certified_key) => {
+                *self.current.write().unwrap_or_else(PoisonError::into_inner) =
+                    (Arc::new(certified_key), modified);
+                info!(
+                    "reloaded TLS certificate '{}'",
+                    self.tls_config.cert_path.display()
+                );
+            }
+            Err(e) => error!("failed to reload TLS certificate (keeping the previous one): {e}"),
+        }
+    }
+}
+
+impl ResolvesServerCert for ReloadingCertResolver {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(Arc::clone(
+            &self
+                .current
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .0,
+        ))
+    }
+}
+
+/// Polls the certificate files until the resolver (i.e. the listener) is dropped.
+async fn watch_cert_files(resolver: Weak<ReloadingCertResolver>, reload_interval: Duration) {
+    loop {
+        tokio::time::sleep(reload_interval).await;
+        let Some(resolver) = resolver.upgrade() else {
+            return;
+        };
+        resolver.reload_if_changed();
+    }
+}
+
+fn load_certified_key(
+    tls_config: &TlsConfig,
+    provider: &CryptoProvider,
+) -> io::Result<CertifiedKey> {
+    let cert_chain: Vec<CertificateDer<'static>> =
+        CertificateDer::pem_file_iter(&tls_config.cert_path)
+            .and_then(Iterator::collect)
+            .map_err(|e| invalid_pem(&tls_config.cert_path, &e))?;
+    if cert_chain.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "'{}' contains no certificates",
+                tls_config.cert_path.display()
+            ),
+        ));
+    }
+
+    let private_key: PrivateKeyDer<'static> = PrivateKeyDer::from_pem_file(&tls_config.key_path)
+        .map_err(|e| invalid_pem(&tls_config.key_path, &e))?;
+    let signing_key = provider
+        .key_provider
+        .load_private_key(private_key)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    // e.g. halfway through a renewal, when only one of the files has been replaced
+    let certified_key: CertifiedKey = CertifiedKey::new(cert_chain, signing_key);
+    certified_key.keys_match().map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "the private key '{}' does not match the certificate '{}': {e}",
+                tls_config.key_path.display(),
+                tls_config.cert_path.display()
+            ),
+        )
+    })?;
+    Ok(certified_key)
+}
+
+fn last_modified(tls_config: &TlsConfig) -> Option<SystemTime> {
+    [&tls_config.cert_path, &tls_config.key_path]
+        .into_iter()
+        .filter_map(|path| {
+            path.metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .max()
+}
+
+fn invalid_pem(path: &Path, e: &rustls_pki_types::pem::Error) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("failed to read '{}': {e}", path.display()),
+    )
+}
@@ -114,4 +114,10 @@ impl TemplateRegistry<'_> {
         let rendered_template: String = self.handlebars.render(name, data)?;
         Ok(rendered_template)
     }
+
+    /// The number of registered templates (layouts, pages and partials).
+    #[must_use]
+    pub fn template_count(&self) -> usize {
+        self.handlebars.get_templates().len()
+    }
 }
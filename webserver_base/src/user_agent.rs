@@ -0,0 +1,176 @@
+use std::fmt::{Display, Formatter};
+
+use tracing::instrument;
+
+/// Broad classification of an HTTP User-Agent.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum UserAgentClass {
+    /// A (presumably human-driven) web browser.
+    Browser,
+
+    /// Search engine crawlers, link preview fetchers, and generic HTTP clients/scripts.
+    Crawler,
+
+    /// Automated browsers (e.g. headless Chrome, Puppeteer, Playwright, Selenium).
+    HeadlessBrowser,
+
+    /// Uptime and synthetic monitoring services.
+    UptimeMonitor,
+
+    /// Missing or blank User-Agent.
+    Empty,
+}
+
+impl UserAgentClass {
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Browser => "browser",
+            Self::Crawler => "crawler",
+            Self::HeadlessBrowser => "headless_browser",
+            Self::UptimeMonitor => "uptime_monitor",
+            Self::Empty => "empty",
+        }
+    }
+
+    /// Whether the User-Agent is anything other than a regular browser.
+    #[must_use]
+    pub const fn is_bot(&self) -> bool {
+        !matches!(self, Self::Browser)
+    }
+}
+
+impl Display for UserAgentClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// What a rule looks for in the (lowercased) User-Agent.
+#[derive(Debug, Clone)]
+enum Pattern {
+    Substring(String),
+
+    /// A word (run of ASCII letters and digits) that ends with the suffix, e.g. "bot" in "Slackbot-LinkExpanding".
+    WordEnding(String),
+}
+
+impl Pattern {
+    fn matches(&self, user_agent: &str) -> bool {
+        match self {
+            Self::Substring(pattern) => user_agent.contains(pattern.as_str()),
+            Self::WordEnding(suffix) => user_agent
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .any(|word| word.ends_with(suffix.as_str())),
+        }
+    }
+}
+
+/// Classifies User-Agents using an ordered list of case-insensitive rules.
+///
+/// The first matching rule wins; User-Agents that match no rule are classified as `UserAgentClass::Browser`.
+#[derive(Debug, Clone)]
+pub struct UserAgentClassifier {
+    rules: Vec<(Pattern, UserAgentClass)>,
+}
+
+impl Default for UserAgentClassifier {
+    fn default() -> Self {
+        // not "electron": desktop apps embed it and their users are real people
+        let headless_browsers: [&str; 6] = [
+            "headlesschrome",
+            "phantomjs",
+            "puppeteer",
+            "playwright",
+            "selenium",
+            "webdriver",
+        ];
+        let uptime_monitors: [&str; 9] = [
+            "uptimerobot",
+            "pingdom",
+            "statuscake",
+            "betteruptime",
+            "uptime-kuma",
+            "site24x7",
+            "newrelicpinger",
+            "datadogsynthetics",
+            "freshping",
+        ];
+        // phones whose model name ends in "bot"
+        let bot_named_browsers: [&str; 1] = ["cubot"];
+        // words ending in these, e.g. "Googlebot", "DuckDuckBot-Https", "Baiduspider"
+        let crawler_word_endings: [&str; 3] = ["bot", "crawler", "spider"];
+        let crawlers: [&str; 13] = [
+            "slurp",
+            "facebookexternalhit",
+            "embedly",
+            "lighthouse",
+            "curl/",
+            "wget/",
+            "python-requests",
+            "python-urllib",
+            "aiohttp",
+            "go-http-client",
+            "okhttp",
+            "axios/",
+            "node-fetch",
+        ];
+
+        let mut classifier: Self = Self::new();
+        for pattern in bot_named_browsers {
+            classifier = classifier.rule(pattern, UserAgentClass::Browser);
+        }
+        for pattern in headless_browsers {
+            classifier = classifier.rule(pattern, UserAgentClass::HeadlessBrowser);
+        }
+        for pattern in uptime_monitors {
+            classifier = classifier.rule(pattern, UserAgentClass::UptimeMonitor);
+        }
+        for suffix in crawler_word_endings {
+            classifier = classifier.word_ending_rule(suffix, UserAgentClass::Crawler);
+        }
+        for pattern in crawlers {
+            classifier = classifier.rule(pattern, UserAgentClass::Crawler);
+        }
+        classifier
+    }
+}
+
+impl UserAgentClassifier {
+    /// Creates a classifier without any rules (see `Default` for the built-in rules).
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// Appends a rule: User-Agents containing `pattern` (case-insensitive) are classified as `class`.
+    #[must_use]
+    pub fn rule(mut self, pattern: &str, class: UserAgentClass) -> Self {
+        self.rules
+            .push((Pattern::Substring(pattern.to_lowercase()), class));
+        self
+    }
+
+    /// Appends a rule: User-Agents containing a word (run of letters and digits) that ends with `suffix`
+    /// (case-insensitive) are classified as `class`.
+    #[must_use]
+    pub fn word_ending_rule(mut self, suffix: &str, class: UserAgentClass) -> Self {
+        self.rules
+            .push((Pattern::WordEnding(suffix.to_lowercase()), class));
+        self
+    }
+
+    #[must_use]
+    #[instrument(skip_all)]
+    pub fn classify(&self, user_agent: &str) -> UserAgentClass {
+        let user_agent: String = user_agent.trim().to_lowercase();
+        if user_agent.is_empty() {
+            return UserAgentClass::Empty;
+        }
+
+        self.rules
+            .iter()
+            .find(|(pattern, _)| pattern.matches(&user_agent))
+            .map_or(UserAgentClass::Browser, |(_, class)| *class)
+    }
+}
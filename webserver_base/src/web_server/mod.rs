@@ -0,0 +1,6 @@
+mod web_app;
+#[expect(clippy::module_inception)]
+mod web_server;
+
+pub use web_app::*;
+pub use web_server::*;
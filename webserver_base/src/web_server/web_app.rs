@@ -0,0 +1,74 @@
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+
+use axum::Router;
+
+use crate::metrics::Metrics;
+use crate::server::Readiness;
+
+type ShutdownHook = Box<dyn FnOnce() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send>;
+
+/// The site that `WebServer::run` serves: its router, the readiness its health endpoint reports, its metrics, and what
+/// to do once it has stopped serving requests.
+pub struct WebApp {
+    pub(super) router: Router,
+    pub(super) readiness: Readiness,
+    pub(super) metrics: Option<Metrics>,
+    pub(super) shutdown_hooks: Vec<(String, ShutdownHook)>,
+}
+
+impl Debug for WebApp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebApp")
+            .field("readiness", &self.readiness)
+            .field("metrics", &self.metrics)
+            .field(
+                "shutdown_hooks",
+                &self
+                    .shutdown_hooks
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<&String>>(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl WebApp {
+    #[must_use]
+    pub fn new(router: Router) -> Self {
+        Self {
+            router,
+            readiness: Readiness::new(),
+            metrics: None,
+            shutdown_hooks: Vec::new(),
+        }
+    }
+
+    /// The readiness flag that the app's health endpoint reports (see `HasReadiness`).
+    #[must_use]
+    pub fn readiness(mut self, readiness: Readiness) -> Self {
+        self.readiness = readiness;
+        self
+    }
+
+    /// Records HTTP metrics and serves `metrics` at `/metrics` (see `WebServer`).
+    #[must_use]
+    pub fn metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Registers a hook to run after the in-flight requests have drained (see `ServerRunner::on_shutdown`).
+    #[must_use]
+    pub fn on_shutdown<F, Fut>(mut self, name: impl Into<String>, hook: F) -> Self
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.shutdown_hooks
+            .push((name.into(), Box::new(move || Box::pin(hook()))));
+        self
+    }
+}
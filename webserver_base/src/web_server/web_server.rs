@@ -0,0 +1,328 @@
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::io;
+use std::time::Duration;
+
+use axum::Router;
+use axum::extract::DefaultBodyLimit;
+use sentry::integrations::tower::NewSentryLayer;
+use sentry::{ClientInitGuard, ClientOptions};
+use tokio::runtime::{Builder, Runtime};
+use tower::ServiceBuilder;
+use tower_http::LatencyUnit;
+use tower_http::trace::{DefaultOnResponse, TraceLayer};
+use tracing::{Level, info, instrument, warn};
+use tracing_subscriber::layer::{Layered, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+use super::WebApp;
+use crate::base_settings::{BaseSettings, LogFormat};
+use crate::metrics::{Metrics, MetricsAccess, metrics_router};
+use crate::request_id::{RequestIdMakeSpan, request_id_middleware};
+use crate::server::{Listener, ListenerConfig, ServerRunner};
+
+/// The subscriber that extra tracing layers (see `WebServer::tracing_layer`) are added to.
+pub type TracingRegistry = Layered<EnvFilter, Registry>;
+
+type TracingLayer = Box<dyn Layer<TracingRegistry> + Send + Sync>;
+type SentryOptionsHook = Box<dyn FnOnce(&mut ClientOptions)>;
+type RuntimeHook = Box<dyn FnOnce(&mut Builder)>;
+type RouterHook = Box<dyn FnOnce(Router) -> Router>;
+type ServerRunnerHook = Box<dyn FnOnce(ServerRunner) -> ServerRunner>;
+
+/// Runs a site the same way in every project:
+///
+/// 1. tracing: the configured log format, filtered by `RUST_LOG` (default `info`), plus Sentry breadcrumbs
+/// 2. Sentry, unless `sentry_dsn` is unset
+/// 3. a multi-threaded Tokio runtime (created after Sentry, so that its hub applies to every worker thread)
+/// 4. the app, wrapped in the standard middleware: Sentry, request IDs (`X-Request-Id`), request logging and a request
+///    body limit
+/// 5. the app's metrics (if any), recorded per route and served at `/metrics`: on `metrics_listen` if set, otherwise
+///    on the main listeners to `metrics_allowlist` only (and not at all if that is empty)
+/// 6. every configured listener, served by a `ServerRunner` (graceful shutdown, then flushing Sentry)
+///
+/// Each step can be customised with the builder methods.
+pub struct WebServer {
+    settings: BaseSettings,
+    log_filter: String,
+    tracing_layers: Vec<TracingLayer>,
+    sentry_options: SentryOptionsHook,
+    runtime: RuntimeHook,
+    body_limit: usize,
+    request_log_level: Level,
+    router: RouterHook,
+    server_runner: ServerRunnerHook,
+}
+
+impl Debug for WebServer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebServer")
+            .field("settings", &self.settings)
+            .field("log_filter", &self.log_filter)
+            .field("body_limit", &self.body_limit)
+            .field("request_log_level", &self.request_log_level)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WebServer {
+    #[must_use]
+    pub fn new(settings: BaseSettings) -> Self {
+        Self {
+            settings,
+            log_filter: String::from("info"),
+            tracing_layers: Vec::new(),
+            sentry_options: Box::new(|_| {}),
+            runtime: Box::new(|_| {}),
+            body_limit: 1024,
+            request_log_level: Level::INFO,
+            router: Box::new(|router| router),
+            server_runner: Box::new(|server_runner| server_runner),
+        }
+    }
+
+    /// The log filter used when `RUST_LOG` is unset (e.g. `info,tower_http=debug`).
+    #[must_use]
+    pub fn log_filter(mut self, log_filter: impl Into<String>) -> Self {
+        self.log_filter = log_filter.into();
+        self
+    }
+
+    /// Adds a tracing layer (e.g. an OpenTelemetry exporter) next to the log output.
+    #[must_use]
+    pub fn tracing_layer<L>(mut self, layer: L) -> Self
+    where
+        L: Layer<TracingRegistry> + Send + Sync + 'static,
+    {
+        self.tracing_layers.push(layer.boxed());
+        self
+    }
+
+    /// Customises the Sentry client, e.g. `|options| options.release = sentry::release_name!()`.
+    #[must_use]
+    pub fn sentry_options(
+        mut self,
+        sentry_options: impl FnOnce(&mut ClientOptions) + 'static,
+    ) -> Self {
+        self.sentry_options = Box::new(sentry_options);
+        self
+    }
+
+    /// Customises the Tokio runtime, e.g. `|builder| { builder.worker_threads(2); }`.
+    #[must_use]
+    pub fn runtime(mut self, runtime: impl FnOnce(&mut Builder) + 'static) -> Self {
+        self.runtime = Box::new(runtime);
+        self
+    }
+
+    /// The maximum request body size in bytes (1 KiB by default); routes can raise it with their own
+    /// `DefaultBodyLimit`.
+    #[must_use]
+    pub const fn body_limit(mut self, body_limit: usize) -> Self {
+        self.body_limit = body_limit;
+        self
+    }
+
+    /// The level that requests and responses are logged at.
+    #[must_use]
+    pub const fn request_log_level(mut self, request_log_level: Level) -> Self {
+        self.request_log_level = request_log_level;
+        self
+    }
+
+    /// Customises the app after the standard middleware is added, e.g. to add outer layers.
+    #[must_use]
+    pub fn router(mut self, router: impl FnOnce(Router) -> Router + 'static) -> Self {
+        self.router = Box::new(router);
+        self
+    }
+
+    /// Customises how the app is served, e.g. `|runner| runner.drain_timeout(Duration::from_secs(20))`.
+    #[must_use]
+    pub fn server_runner(
+        mut self,
+        server_runner: impl FnOnce(ServerRunner) -> ServerRunner + 'static,
+    ) -> Self {
+        self.server_runner = Box::new(server_runner);
+        self
+    }
+
+    /// Sets everything up, builds the app with `app` and serves it until shutdown.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the runtime cannot be created, `app` fails, a listener cannot be bound, or a listener
+    /// fails while serving.
+    pub fn run<F, Fut, E>(self, app: F) -> Result<(), E>
+    where
+        F: FnOnce(BaseSettings) -> Fut,
+        Fut: Future<Output = Result<WebApp, E>>,
+        E: From<io::Error>,
+    {
+        init_tracing(
+            self.settings.log_format,
+            &self.log_filter,
+            self.tracing_layers,
+        );
+        let _sentry_guard: Option<ClientInitGuard> =
+            init_sentry(&self.settings, self.sentry_options);
+        if self.settings.reported_analytics_domain().is_none() {
+            info!(
+                "analytics are disabled in the {} environment: no analytics domain is configured (or `ANALYTICS_ENABLED` is false)",
+                self.settings.environment
+            );
+        }
+
+        let mut runtime_builder: Builder = Builder::new_multi_thread();
+        runtime_builder.enable_all();
+        (self.runtime)(&mut runtime_builder);
+        let runtime: Runtime = runtime_builder.build()?;
+
+        runtime.block_on(async move {
+            let web_app: WebApp = app(self.settings.clone()).await?;
+            serve(
+                &self.settings,
+                web_app,
+                self.body_limit,
+                self.request_log_level,
+                self.router,
+                self.server_runner,
+            )
+            .await?;
+            Ok(())
+        })
+    }
+}
+
+/// Adds the standard middleware (and metrics) to the app, binds its listeners and serves it until shutdown.
+async fn serve(
+    settings: &BaseSettings,
+    web_app: WebApp,
+    body_limit: usize,
+    request_log_level: Level,
+    router_hook: RouterHook,
+    server_runner_hook: ServerRunnerHook,
+) -> io::Result<()> {
+    let mut router: Router = web_app.router;
+    if let Some(metrics) = &web_app.metrics {
+        // fail closed: the main listeners are public, so only serve `/metrics` there to an explicit allowlist
+        if settings.metrics_listen.is_none() {
+            if settings.metrics_allowlist.is_empty() {
+                info!("not serving /metrics: neither METRICS_LISTEN nor METRICS_ALLOWLIST is set");
+            } else {
+                router = router.merge(metrics_router(
+                    metrics.clone(),
+                    MetricsAccess::Allowlist(settings.metrics_allowlist.clone()),
+                ));
+            }
+        }
+        router = router.layer(axum::middleware::from_fn_with_state(
+            metrics.clone(),
+            Metrics::middleware,
+        ));
+    }
+    let router: Router = router_hook(standard_middleware(router, body_limit, request_log_level));
+
+    let listeners: Vec<Listener> = settings
+        .listeners
+        .iter()
+        .map(|listener| bind_listener(listener, settings))
+        .collect::<io::Result<Vec<Listener>>>()?;
+    let mut server_runner: ServerRunner =
+        ServerRunner::new(listeners, router).readiness(web_app.readiness);
+    if let (Some(metrics), Some(metrics_listen)) = (web_app.metrics, &settings.metrics_listen) {
+        server_runner = server_runner.additional_listener(
+            bind_listener(metrics_listen, settings)?,
+            metrics_router(metrics, MetricsAccess::Internal),
+        );
+    }
+
+    for (name, hook) in web_app.shutdown_hooks {
+        server_runner = server_runner.on_shutdown(name, hook);
+    }
+    if settings.sentry_dsn.is_some() {
+        server_runner = server_runner.on_shutdown("sentry", flush_sentry);
+    }
+    server_runner_hook(server_runner).run().await
+}
+
+fn bind_listener(listener: &ListenerConfig, settings: &BaseSettings) -> io::Result<Listener> {
+    Listener::bind(listener, settings.tls.as_ref()).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("failed to bind listener '{listener}': {e}"),
+        )
+    })
+}
+
+#[instrument(skip_all)]
+fn init_tracing(log_format: LogFormat, log_filter: &str, mut tracing_layers: Vec<TracingLayer>) {
+    let fmt_layer: TracingLayer = match log_format {
+        LogFormat::Pretty => tracing_subscriber::fmt::Layer::default().pretty().boxed(),
+        LogFormat::Compact => tracing_subscriber::fmt::Layer::default().compact().boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::Layer::default().json().boxed(),
+    };
+    let env_filter: EnvFilter = EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new(log_filter))
+        .unwrap_or_else(|e| panic!("invalid log filter '{log_filter}': {e}"));
+
+    tracing_layers.insert(0, fmt_layer);
+
+    let result = Registry::default()
+        .with(env_filter)
+        .with(tracing_layers)
+        .with(sentry::integrations::tracing::layer())
+        .try_init();
+    if let Err(e) = result {
+        // e.g. when a test already installed a subscriber
+        warn!("tracing is already initialized: {e}");
+    }
+}
+
+fn init_sentry(
+    settings: &BaseSettings,
+    sentry_options: SentryOptionsHook,
+) -> Option<ClientInitGuard> {
+    let Some(sentry_dsn) = &settings.sentry_dsn else {
+        info!("error monitoring is disabled: `SENTRY_DSN` is not set");
+        return None;
+    };
+
+    let mut options: ClientOptions = ClientOptions {
+        environment: Some(settings.environment.to_string().into()),
+        attach_stacktrace: true,
+        ..Default::default()
+    };
+    sentry_options(&mut options);
+    Some(sentry::init((sentry_dsn.expose().clone(), options)))
+}
+
+fn standard_middleware(router: Router, body_limit: usize, request_log_level: Level) -> Router {
+    router.layer(
+        ServiceBuilder::new()
+            // a Sentry hub per request, so the request ID tag does not leak into other requests
+            .layer(NewSentryLayer::new_from_top())
+            .layer(axum::middleware::from_fn(request_id_middleware))
+            .layer(
+                TraceLayer::new_for_http()
+                    .make_span_with(RequestIdMakeSpan::new(request_log_level))
+                    .on_response(
+                        DefaultOnResponse::new()
+                            .level(request_log_level)
+                            .latency_unit(LatencyUnit::Micros),
+                    ),
+            )
+            .layer(DefaultBodyLimit::max(body_limit)),
+    )
+}
+
+/// Sends the events that Sentry has queued (flushing blocks, so it runs off the runtime's worker threads).
+async fn flush_sentry() {
+    let _ = tokio::task::spawn_blocking(|| {
+        if let Some(client) = sentry::Hub::main().client() {
+            client.flush(Some(Duration::from_secs(2)));
+        }
+    })
+    .await;
+}
@@ -0,0 +1,463 @@
+mod common;
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::Router;
+use axum::body::Body;
+use axum::extract::DefaultBodyLimit;
+use axum::extract::connect_info::MockConnectInfo;
+use axum::http::{
+    HeaderMap, HeaderValue, Request, StatusCode,
+    header::{CONTENT_TYPE, USER_AGENT},
+};
+use axum::response::{Html, Response};
+use axum::routing::get;
+use tower::ServiceExt;
+use webserver_base::{
+    api_router::{
+        HasAnalytics, HasBuildInfo, HasFrontendErrorReporter, HasHealthChecks, HasReadiness,
+        HasSettings, api_router,
+    },
+    axum_plausible_analytics::{
+        AnalyticsError, AnalyticsEvent, AnalyticsQueueConfig, AnalyticsStats,
+        AxumPlausibleAnalyticsHandler, MemorySink, PageviewMiddleware, PayloadValidator,
+        QueuedEvent, RequestPayload, Revenue,
+    },
+    base_settings::{BaseSettings, Environment},
+    build_info::BuildInfo,
+    frontend_error_logger::{FrontendErrorReporter, SourceMapResolver},
+    health::HealthChecks,
+    ip::IpAnonymization,
+    server::Readiness,
+};
+
+fn settings() -> BaseSettings {
+    common::settings(&[("ANALYTICS_DOMAIN", "example.com")])
+}
+
+#[tokio::test]
+async fn track_sends_server_side_event_to_sink() {
+    let sink: Arc<MemorySink> = Arc::new(MemorySink::new());
+    let handler: AxumPlausibleAnalyticsHandler = AxumPlausibleAnalyticsHandler::new_with_sink(
+        sink.clone(),
+        IpAnonymization::Truncate,
+        AnalyticsQueueConfig::default(),
+    );
+
+    let mut headers: HeaderMap = HeaderMap::new();
+    headers.insert(USER_AGENT, HeaderValue::from_static("test-agent"));
+    let addr: SocketAddr = SocketAddr::new(IpAddr::from([203, 0, 113, 42]), 1234);
+
+    handler
+        .track(
+            &headers,
+            &settings(),
+            addr,
+            AnalyticsEvent::new("signup", "https://www.example.com/signup")
+                .prop("plan", String::from("pro"))
+                .revenue(Revenue::new(String::from("USD"), String::from("9.99"))),
+        )
+        .unwrap();
+    handler.shutdown(Duration::from_secs(1)).await;
+
+    let events: Vec<QueuedEvent> = sink.events();
+    assert_eq!(1, events.len());
+    assert_eq!("signup", events[0].body.name);
+    assert_eq!("example.com", events[0].body.domain);
+    assert_eq!("test-agent", events[0].headers.user_agent);
+    assert_eq!("203.0.113.42", events[0].headers.x_forwarded_for);
+    assert!(events[0].body.revenue.is_some());
+    assert_eq!(
+        AnalyticsStats {
+            forwarded: 1,
+            ..AnalyticsStats::default()
+        },
+        handler.stats()
+    );
+}
+
+#[tokio::test]
+async fn non_production_without_domain_drops_events() {
+    let sink: Arc<MemorySink> = Arc::new(MemorySink::new());
+    let handler: AxumPlausibleAnalyticsHandler = AxumPlausibleAnalyticsHandler::new_with_sink(
+        sink.clone(),
+        IpAnonymization::Truncate,
+        AnalyticsQueueConfig::default(),
+    );
+
+    let mut settings: BaseSettings = settings();
+    settings.environment = Environment::Development;
+    let mut headers: HeaderMap = HeaderMap::new();
+    headers.insert(USER_AGENT, HeaderValue::from_static("test-agent"));
+    let addr: SocketAddr = SocketAddr::new(IpAddr::from([203, 0, 113, 42]), 1234);
+
+    handler
+        .track(
+            &headers,
+            &settings,
+            addr,
+            AnalyticsEvent::pageview("https://www.example.com/"),
+        )
+        .unwrap();
+    handler.shutdown(Duration::from_secs(1)).await;
+
+    assert!(sink.events().is_empty());
+}
+
+#[tokio::test]
+async fn dedup_pageviews_drops_repeat_views() {
+    let sink: Arc<MemorySink> = Arc::new(MemorySink::new());
+    let handler: AxumPlausibleAnalyticsHandler = AxumPlausibleAnalyticsHandler::new_with_sink(
+        sink.clone(),
+        IpAnonymization::Truncate,
+        AnalyticsQueueConfig::default(),
+    )
+    .dedup_pageviews(Duration::from_secs(30));
+
+    let mut headers: HeaderMap = HeaderMap::new();
+    headers.insert(USER_AGENT, HeaderValue::from_static("test-agent"));
+    let addr: SocketAddr = SocketAddr::new(IpAddr::from([203, 0, 113, 42]), 1234);
+
+    // server-side (middleware) and client-side (beacon) pageviews of the same page
+    for url in ["https://example.com/about", "https://www.example.com/about"] {
+        handler
+            .track(&headers, &settings(), addr, AnalyticsEvent::pageview(url))
+            .unwrap();
+    }
+    handler
+        .track(
+            &headers,
+            &settings(),
+            addr,
+            AnalyticsEvent::pageview("https://www.example.com/contact"),
+        )
+        .unwrap();
+    handler.shutdown(Duration::from_secs(1)).await;
+
+    assert_eq!(2, sink.events().len());
+}
+
+#[tokio::test]
+async fn dedup_pageviews_records_views_again_after_the_window() {
+    let sink: Arc<MemorySink> = Arc::new(MemorySink::new());
+    let handler: AxumPlausibleAnalyticsHandler = AxumPlausibleAnalyticsHandler::new_with_sink(
+        sink.clone(),
+        IpAnonymization::Truncate,
+        AnalyticsQueueConfig::default(),
+    )
+    .dedup_pageviews(Duration::from_millis(50));
+
+    let mut headers: HeaderMap = HeaderMap::new();
+    headers.insert(USER_AGENT, HeaderValue::from_static("test-agent"));
+    let addr: SocketAddr = SocketAddr::new(IpAddr::from([203, 0, 113, 42]), 1234);
+    let track = || {
+        handler
+            .track(
+                &headers,
+                &settings(),
+                addr,
+                AnalyticsEvent::pageview("https://www.example.com/about"),
+            )
+            .unwrap();
+    };
+
+    track();
+    track();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    track();
+    track();
+    handler.shutdown(Duration::from_secs(1)).await;
+
+    assert_eq!(2, sink.events().len());
+}
+
+#[tokio::test]
+async fn dedup_pageviews_window_starts_at_the_first_view() {
+    let sink: Arc<MemorySink> = Arc::new(MemorySink::new());
+    let handler: AxumPlausibleAnalyticsHandler = AxumPlausibleAnalyticsHandler::new_with_sink(
+        sink.clone(),
+        IpAnonymization::Truncate,
+        AnalyticsQueueConfig::default(),
+    )
+    .dedup_pageviews(Duration::from_millis(100));
+
+    let mut headers: HeaderMap = HeaderMap::new();
+    headers.insert(USER_AGENT, HeaderValue::from_static("test-agent"));
+    let addr: SocketAddr = SocketAddr::new(IpAddr::from([203, 0, 113, 42]), 1234);
+    let track = || {
+        handler
+            .track(
+                &headers,
+                &settings(),
+                addr,
+                AnalyticsEvent::pageview("https://www.example.com/about"),
+            )
+            .unwrap();
+    };
+
+    // reloading more often than the window still records a view per window
+    track();
+    tokio::time::sleep(Duration::from_millis(70)).await;
+    track();
+    tokio::time::sleep(Duration::from_millis(70)).await;
+    track();
+    handler.shutdown(Duration::from_secs(1)).await;
+
+    assert_eq!(2, sink.events().len());
+}
+
+#[tokio::test]
+async fn pageview_middleware_matches_whole_path_segments() {
+    let handler: Arc<AxumPlausibleAnalyticsHandler> =
+        Arc::new(AxumPlausibleAnalyticsHandler::new_with_sink(
+            Arc::new(MemorySink::new()),
+            IpAnonymization::Truncate,
+            AnalyticsQueueConfig::default(),
+        ));
+    let pageviews: PageviewMiddleware =
+        PageviewMiddleware::new(handler, settings()).include("/blog/");
+
+    assert!(pageviews.is_tracked_path("/blog"));
+    assert!(pageviews.is_tracked_path("/blog/hello"));
+    assert!(!pageviews.is_tracked_path("/blogroll"));
+
+    let pageviews: PageviewMiddleware = pageviews.include("/apiary");
+    assert!(pageviews.is_tracked_path("/apiary"));
+    assert!(!pageviews.is_tracked_path("/api"));
+    assert!(!pageviews.is_tracked_path("/api/v1/scitylana"));
+}
+
+#[tokio::test]
+async fn pageview_middleware_falls_back_to_forwarding_headers_without_connect_info() {
+    let sink: Arc<MemorySink> = Arc::new(MemorySink::new());
+    let handler: Arc<AxumPlausibleAnalyticsHandler> =
+        Arc::new(AxumPlausibleAnalyticsHandler::new_with_sink(
+            sink.clone(),
+            IpAnonymization::Truncate,
+            AnalyticsQueueConfig::default(),
+        ));
+    let app: Router = Router::new()
+        .route("/about", get(|| async { Html("<p>about</p>") }))
+        .layer(axum::middleware::from_fn_with_state(
+            Arc::new(PageviewMiddleware::new(Arc::clone(&handler), settings())),
+            PageviewMiddleware::middleware,
+        ));
+
+    let request: Request<Body> = Request::builder()
+        .uri("/about")
+        .header(USER_AGENT, "test-agent")
+        .header("x-forwarded-for", "203.0.113.42")
+        .body(Body::empty())
+        .unwrap();
+    let response: Response = app.oneshot(request).await.unwrap();
+    assert_eq!(StatusCode::OK, response.status());
+    handler.shutdown(Duration::from_secs(1)).await;
+
+    let events: Vec<QueuedEvent> = sink.events();
+    assert_eq!(1, events.len());
+    assert_eq!("https://www.example.com/about", events[0].body.url);
+    assert_eq!("203.0.113.42", events[0].headers.x_forwarded_for);
+}
+
+#[tokio::test]
+async fn full_queue_accepts_and_counts_dropped_beacon_events() {
+    let sink: Arc<MemorySink> = Arc::new(MemorySink::new());
+    let handler: Arc<AxumPlausibleAnalyticsHandler> =
+        Arc::new(AxumPlausibleAnalyticsHandler::new_with_sink(
+            sink.clone(),
+            IpAnonymization::Truncate,
+            AnalyticsQueueConfig {
+                capacity: 1,
+                ..AnalyticsQueueConfig::default()
+            },
+        ));
+    let addr: SocketAddr = SocketAddr::new(IpAddr::from([203, 0, 113, 42]), 1234);
+
+    // the worker cannot run in between, so the second event finds the queue full
+    for page in ["about", "contact"] {
+        let (status, _) = Arc::clone(&handler)
+            .handle(
+                HeaderMap::new(),
+                settings(),
+                addr,
+                request_payload(&format!("https://www.example.com/{page}")),
+            )
+            .await;
+        assert_eq!(StatusCode::ACCEPTED, status);
+    }
+    assert_eq!(1, handler.stats().dropped);
+
+    handler.shutdown(Duration::from_secs(1)).await;
+    assert_eq!(1, sink.events().len());
+}
+
+#[tokio::test]
+async fn disabled_analytics_accept_beacon_payloads_without_validating_them() {
+    let sink: Arc<MemorySink> = Arc::new(MemorySink::new());
+    let handler: Arc<AxumPlausibleAnalyticsHandler> =
+        Arc::new(AxumPlausibleAnalyticsHandler::new_with_sink(
+            sink.clone(),
+            IpAnonymization::Truncate,
+            AnalyticsQueueConfig::default(),
+        ));
+    let mut settings: BaseSettings = settings();
+    settings.analytics_domain = None;
+    let addr: SocketAddr = SocketAddr::new(IpAddr::from([203, 0, 113, 42]), 1234);
+
+    // a foreign URL would be rejected if analytics were enabled
+    let (status, _) = Arc::clone(&handler)
+        .handle(
+            HeaderMap::new(),
+            settings,
+            addr,
+            request_payload("https://evil.example.org/"),
+        )
+        .await;
+    assert_eq!(StatusCode::ACCEPTED, status);
+
+    handler.shutdown(Duration::from_secs(1)).await;
+    assert!(sink.events().is_empty());
+    assert_eq!(AnalyticsStats::default(), handler.stats());
+}
+
+struct ApiState {
+    settings: BaseSettings,
+    analytics: Arc<AxumPlausibleAnalyticsHandler>,
+    frontend_error_reporter: FrontendErrorReporter,
+    readiness: Readiness,
+    health_checks: HealthChecks,
+    build_info: BuildInfo,
+}
+
+impl HasSettings for ApiState {
+    fn settings(&self) -> &BaseSettings {
+        &self.settings
+    }
+}
+
+impl HasAnalytics for ApiState {
+    fn analytics(&self) -> &Arc<AxumPlausibleAnalyticsHandler> {
+        &self.analytics
+    }
+}
+
+impl HasFrontendErrorReporter for ApiState {
+    fn frontend_error_reporter(&self) -> &FrontendErrorReporter {
+        &self.frontend_error_reporter
+    }
+}
+
+impl HasReadiness for ApiState {
+    fn readiness(&self) -> &Readiness {
+        &self.readiness
+    }
+}
+
+impl HasHealthChecks for ApiState {
+    fn health_checks(&self) -> &HealthChecks {
+        &self.health_checks
+    }
+}
+
+impl HasBuildInfo for ApiState {
+    fn build_info(&self) -> &BuildInfo {
+        &self.build_info
+    }
+}
+
+#[tokio::test]
+async fn beacon_route_accepts_payloads_over_the_server_body_limit() {
+    let sink: Arc<MemorySink> = Arc::new(MemorySink::new());
+    let analytics: Arc<AxumPlausibleAnalyticsHandler> =
+        Arc::new(AxumPlausibleAnalyticsHandler::new_with_sink(
+            sink.clone(),
+            IpAnonymization::Truncate,
+            AnalyticsQueueConfig::default(),
+        ));
+    let app: Router = api_router::<ApiState>()
+        .with_state(Arc::new(ApiState {
+            settings: settings(),
+            analytics: Arc::clone(&analytics),
+            frontend_error_reporter: FrontendErrorReporter::new(
+                settings(),
+                SourceMapResolver::default(),
+            ),
+            readiness: Readiness::new(),
+            health_checks: HealthChecks::new(),
+            build_info: BuildInfo::new("1.2.3", None, None, None),
+        }))
+        // the `WebServer` default
+        .layer(DefaultBodyLimit::max(1024))
+        .layer(MockConnectInfo(SocketAddr::new(
+            IpAddr::from([203, 0, 113, 42]),
+            1234,
+        )));
+    let post = |path_length: usize| {
+        let body: String = format!(
+            "user_agent=test-agent&url=https://www.example.com/{}&referrer=&screen_width=1280",
+            "a".repeat(path_length)
+        );
+        app.clone().oneshot(
+            Request::post("/api/v1/scitylana")
+                .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    // over 1 KiB, but within the validator's limits
+    let response: Response = post(1500).await.unwrap();
+    assert_eq!(StatusCode::ACCEPTED, response.status());
+
+    // over the validator's limits: its error, not a bare 413
+    let response: Response = post(4096).await.unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+
+    analytics.shutdown(Duration::from_secs(1)).await;
+    assert_eq!(1, sink.events().len());
+}
+
+fn request_payload(url: &str) -> RequestPayload {
+    RequestPayload {
+        user_agent: String::from("test-agent"),
+        url: url.to_string(),
+        referrer: String::from("https://news.example.org/item?id=1#comments"),
+        screen_width: 1280,
+        name: None,
+        props: None,
+        revenue_currency: None,
+        revenue_amount: None,
+    }
+}
+
+#[test]
+fn payload_validator_sanitises_urls() {
+    let payload: RequestPayload = PayloadValidator::default()
+        .validate(
+            request_payload("https://example.com/blog?utm_source=hn&token=secret#top"),
+            &settings(),
+        )
+        .unwrap();
+
+    assert_eq!("https://example.com/blog?utm_source=hn", payload.url);
+    assert_eq!("https://news.example.org/item", payload.referrer);
+}
+
+#[test]
+fn payload_validator_rejects_foreign_and_oversized_payloads() {
+    let validator: PayloadValidator = PayloadValidator::default();
+
+    assert!(matches!(
+        validator.validate(request_payload("https://spam.example.net/"), &settings()),
+        Err(AnalyticsError::ForeignUrl(_))
+    ));
+    assert!(matches!(
+        validator.validate(
+            request_payload(&format!("https://example.com/{}", "a".repeat(4096))),
+            &settings()
+        ),
+        Err(AnalyticsError::InvalidPayload(_))
+    ));
+}
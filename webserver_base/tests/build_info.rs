@@ -0,0 +1,24 @@
+use webserver_base::build_info::{BuildInfo, UNKNOWN};
+
+#[test]
+fn build_info_falls_back_to_unknown() {
+    // e.g. built outside a git checkout, with a broken `$RUSTC`
+    let build_info: BuildInfo = BuildInfo::new(env!("CARGO_PKG_VERSION"), None, None, None);
+    assert_eq!(env!("CARGO_PKG_VERSION"), build_info.version);
+    assert_eq!(UNKNOWN, build_info.git_commit);
+    assert_eq!(UNKNOWN, build_info.build_timestamp);
+    assert_eq!(UNKNOWN, build_info.rustc_version);
+
+    let build_info: BuildInfo = BuildInfo::new(
+        "1.2.3",
+        Some("0123456789ab"),
+        Some(""),
+        Some("rustc 1.93.0"),
+    );
+    assert_eq!("0123456789ab", build_info.git_commit);
+    assert_eq!(UNKNOWN, build_info.build_timestamp);
+    assert_eq!(
+        "version 1.2.3 (commit 0123456789ab, built unknown with rustc 1.93.0)",
+        build_info.to_string()
+    );
+}
@@ -0,0 +1,27 @@
+use webserver_base::base_settings::{BaseSettings, SettingsLoader};
+
+/// Production settings for `https://www.example.com`, with `env_vars` (e.g. `("ANALYTICS_DOMAIN", "example.com")`)
+/// set on top; loaded like a deployment's, so every other setting keeps its default.
+///
+/// # Panics
+///
+/// Panics if the settings are invalid.
+pub fn settings(env_vars: &[(&str, &str)]) -> BaseSettings {
+    let defaults: [(&str, &str); 5] = [
+        ("ENVIRONMENT", "production"),
+        ("PROJECT_NAME", "test"),
+        ("PROJECT_DESCRIPTION", "test"),
+        ("PROJECT_KEYWORDS", "test"),
+        ("HOME_URL", "https://www.example.com"),
+    ];
+
+    SettingsLoader::new()
+        .env_vars(
+            defaults
+                .iter()
+                .chain(env_vars)
+                .map(|(name, value)| ((*name).to_string(), (*value).to_string())),
+        )
+        .load()
+        .unwrap()
+}
@@ -0,0 +1,2 @@
+function a(b){throw new Error("comments failed: "+b)}
+//# sourceMappingURL=main.js.map
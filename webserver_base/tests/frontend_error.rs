@@ -0,0 +1,308 @@
+mod common;
+
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use axum::http::{HeaderMap, HeaderValue};
+use chrono::{TimeZone, Utc};
+use webserver_base::{
+    base_settings::BaseSettings,
+    cache_buster::CacheBuster,
+    frontend_error_logger::{
+        FrontendErrorPayload, FrontendErrorReporter, FrontendErrorSeverity, FrontendErrorStats,
+        FrontendErrorThrottleConfig, SourceMapResolver, StackFrame, parse_stack_trace,
+    },
+};
+
+fn settings() -> BaseSettings {
+    common::settings(&[])
+}
+
+fn payload(message: &str) -> FrontendErrorPayload {
+    serde_json::from_value(serde_json::json!({
+        "sourceFile": "https://example.com/static/script/main.abc123.js",
+        "lineNumber": 1,
+        "columnNumber": 2345,
+        "message": message,
+        "stackTrace": null,
+        "currentUrl": "https://example.com/",
+        "timestamp": "2026-10-19T10:34:56.000Z"
+    }))
+    .unwrap()
+}
+
+/// Sends `reports` (client address, message) and returns the resulting stats.
+fn report_all(config: FrontendErrorThrottleConfig, reports: &[(&str, &str)]) -> FrontendErrorStats {
+    let reporter: FrontendErrorReporter =
+        FrontendErrorReporter::new(settings(), SourceMapResolver::default()).throttle(config);
+    for (addr, message) in reports {
+        reporter.report(
+            &HeaderMap::new(),
+            addr.parse::<SocketAddr>().unwrap(),
+            &payload(message),
+        );
+    }
+    reporter.stats()
+}
+
+#[test]
+fn parse_v8_stack_trace() {
+    let stack_trace: &str = "TypeError: Cannot read properties of undefined (reading 'x')
+    at render (https://example.com/static/script/main.abc123.js:1:2345)
+    at https://example.com/static/script/main.abc123.js:1:99";
+
+    assert_eq!(
+        vec![
+            StackFrame {
+                function: Some(String::from("render")),
+                file: String::from("https://example.com/static/script/main.abc123.js"),
+                line: 1,
+                column: 2345,
+            },
+            StackFrame {
+                function: None,
+                file: String::from("https://example.com/static/script/main.abc123.js"),
+                line: 1,
+                column: 99,
+            },
+        ],
+        parse_stack_trace(stack_trace)
+    );
+}
+
+#[test]
+fn parse_gecko_stack_trace() {
+    let stack_trace: &str = "render@https://example.com/static/script/main.abc123.js:1:2345
+@https://example.com/static/script/main.abc123.js:1:99
+";
+
+    let frames: Vec<StackFrame> = parse_stack_trace(stack_trace);
+    assert_eq!(2, frames.len());
+    assert_eq!(Some(String::from("render")), frames[0].function);
+    assert_eq!(None, frames[1].function);
+    assert_eq!(99, frames[1].column);
+}
+
+#[test]
+fn resolve_frames_through_source_map() {
+    // `CacheBuster::gen_cache` renames the files, so work on a copy of the fixture
+    let dir: PathBuf =
+        std::env::temp_dir().join(format!("webserver-base-source-map-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for file in ["main.js", "main.js.map"] {
+        fs::copy(
+            Path::new("tests/fixtures/source_map").join(file),
+            dir.join(file),
+        )
+        .unwrap();
+    }
+    let mut cache_buster: CacheBuster = CacheBuster::new(&dir.to_string_lossy());
+    cache_buster.gen_cache();
+    let resolver: SourceMapResolver = SourceMapResolver::new(&cache_buster);
+    let file: String = format!(
+        "https://example.com{}",
+        cache_buster.get_file(&dir.join("main.js").to_string_lossy())
+    );
+
+    // `throw new Error(...)` inside the minified `function a(b)`, which was `renderComments(postId)`
+    let frame: StackFrame = StackFrame {
+        function: Some(String::from("a")),
+        file: file.clone(),
+        line: 1,
+        column: 25,
+    };
+    assert_eq!(
+        StackFrame {
+            function: Some(String::from("renderComments")),
+            file: String::from("../../src/main.ts"),
+            line: 2,
+            column: 13,
+        },
+        resolver.resolve(&frame)
+    );
+
+    // the minified name is kept if its declaration cannot be found, rather than naming the identifier at the error
+    let frame: StackFrame = StackFrame {
+        function: Some(String::from("c")),
+        file,
+        line: 1,
+        column: 51,
+    };
+    assert_eq!(
+        StackFrame {
+            function: Some(String::from("c")),
+            file: String::from("../../src/main.ts"),
+            line: 2,
+            column: 41,
+        },
+        resolver.resolve(&frame)
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn deserialize_legacy_payload() {
+    let payload: FrontendErrorPayload = serde_json::from_str(
+        r#"{
+            "sourceFile": "https://example.com/static/script/main.abc123.js",
+            "lineNumber": 1,
+            "columnNumber": 2345,
+            "message": "Uncaught TypeError: x is undefined",
+            "stackTrace": null,
+            "currentUrl": "https://example.com/",
+            "timestamp": "Mon Oct 19 2026 12:34:56 GMT+0200 (Central European Summer Time)"
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        Some(Utc.with_ymd_and_hms(2026, 10, 19, 10, 34, 56).unwrap()),
+        payload.timestamp()
+    );
+    assert_eq!(FrontendErrorSeverity::Error, payload.severity());
+    assert_eq!(1, payload.stack_frames().len());
+}
+
+#[test]
+fn deserialize_extended_payload() {
+    let payload: FrontendErrorPayload = serde_json::from_str(
+        r#"{
+            "sourceFile": null,
+            "lineNumber": null,
+            "columnNumber": null,
+            "message": "quota exceeded",
+            "stackTrace": null,
+            "currentUrl": "https://example.com/",
+            "timestamp": "2026-10-19T10:34:56.000Z",
+            "severity": "warning",
+            "name": "QuotaExceededError",
+            "userAgent": "Mozilla/5.0",
+            "viewport": { "width": 1280, "height": 720 },
+            "breadcrumbs": [{ "timestamp": "2026-10-19T10:34:50.000Z", "category": "click", "message": "button#save" }],
+            "context": { "route": "/settings" }
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        Some(Utc.with_ymd_and_hms(2026, 10, 19, 10, 34, 56).unwrap()),
+        payload.timestamp()
+    );
+    assert_eq!(FrontendErrorSeverity::Warning, payload.severity());
+    assert_eq!(Some("Mozilla/5.0"), payload.user_agent());
+}
+
+#[tokio::test]
+async fn throttle_limits_reports_per_fingerprint_and_client() {
+    let config: FrontendErrorThrottleConfig = FrontendErrorThrottleConfig {
+        window: Duration::from_mins(1),
+        max_reports_per_fingerprint: 2,
+        max_reports_per_client: 3,
+        sample_rate: 1.0,
+    };
+
+    // the same error from different clients
+    assert_eq!(
+        FrontendErrorStats {
+            reported: 2,
+            throttled: 3,
+        },
+        report_all(
+            config,
+            &[
+                ("203.0.113.1:1234", "x is undefined"),
+                ("203.0.113.2:1234", "x is undefined"),
+                ("203.0.113.3:1234", "x is undefined"),
+                ("203.0.113.4:1234", "x is undefined"),
+                ("203.0.113.5:1234", "x is undefined"),
+            ],
+        )
+    );
+
+    // different errors from the same client
+    assert_eq!(
+        FrontendErrorStats {
+            reported: 3,
+            throttled: 2,
+        },
+        report_all(
+            config,
+            &[
+                ("203.0.113.1:1234", "a is undefined"),
+                ("203.0.113.1:1234", "b is undefined"),
+                ("203.0.113.1:1234", "c is undefined"),
+                ("203.0.113.1:1234", "d is undefined"),
+                ("203.0.113.1:1234", "e is undefined"),
+            ],
+        )
+    );
+}
+
+#[tokio::test]
+async fn throttle_keys_clients_by_a_single_ip_address() {
+    let config: FrontendErrorThrottleConfig = FrontendErrorThrottleConfig {
+        max_reports_per_client: 2,
+        ..FrontendErrorThrottleConfig::default()
+    };
+    let report_forwarded_for = |peer: &str| -> FrontendErrorStats {
+        let reporter: FrontendErrorReporter =
+            FrontendErrorReporter::new(settings(), SourceMapResolver::default()).throttle(config);
+        for (i, message) in ["a is undefined", "b is undefined", "c is undefined"]
+            .into_iter()
+            .enumerate()
+        {
+            let mut headers: HeaderMap = HeaderMap::new();
+            headers.insert(
+                "X-Forwarded-For",
+                HeaderValue::from_str(&format!("198.51.100.{i}")).unwrap(),
+            );
+            reporter.report(&headers, peer.parse().unwrap(), &payload(message));
+        }
+        reporter.stats()
+    };
+
+    // a client connecting directly cannot look new by varying its forwarding headers
+    assert_eq!(
+        FrontendErrorStats {
+            reported: 2,
+            throttled: 1,
+        },
+        report_forwarded_for("203.0.113.1:1234")
+    );
+
+    // but clients behind a reverse proxy are told apart by them
+    assert_eq!(
+        FrontendErrorStats {
+            reported: 3,
+            throttled: 0,
+        },
+        report_forwarded_for("127.0.0.1:1234")
+    );
+}
+
+#[tokio::test]
+async fn throttle_samples_reports() {
+    let reports: [(&str, &str); 3] = [
+        ("203.0.113.1:1234", "a is undefined"),
+        ("203.0.113.2:1234", "b is undefined"),
+        ("203.0.113.3:1234", "c is undefined"),
+    ];
+
+    let sample_none: FrontendErrorStats = report_all(
+        FrontendErrorThrottleConfig {
+            sample_rate: 0.0,
+            ..FrontendErrorThrottleConfig::default()
+        },
+        &reports,
+    );
+    assert_eq!(0, sample_none.reported);
+    assert_eq!(3, sample_none.throttled);
+
+    let sample_all: FrontendErrorStats =
+        report_all(FrontendErrorThrottleConfig::default(), &reports);
+    assert_eq!(3, sample_all.reported);
+    assert_eq!(0, sample_all.throttled);
+}
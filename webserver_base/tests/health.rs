@@ -0,0 +1,150 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::Router;
+use axum::body::{Body, to_bytes};
+use axum::http::{Request, StatusCode};
+use axum::response::Response;
+use axum::routing::get;
+use serde_json::Value;
+use tower::ServiceExt;
+use webserver_base::{
+    api_router::{HasHealthChecks, HasReadiness, health_live, health_ready},
+    health::{HealthCheck, HealthCheckFuture, HealthChecks, TemplateRegistryCheck},
+    server::Readiness,
+    templates::template_registry::TemplateRegistry,
+};
+
+struct TestState {
+    readiness: Readiness,
+    health_checks: HealthChecks,
+}
+
+impl HasReadiness for TestState {
+    fn readiness(&self) -> &Readiness {
+        &self.readiness
+    }
+}
+
+impl HasHealthChecks for TestState {
+    fn health_checks(&self) -> &HealthChecks {
+        &self.health_checks
+    }
+}
+
+struct FailingCheck;
+
+impl HealthCheck for FailingCheck {
+    fn name(&self) -> &'static str {
+        "failing"
+    }
+
+    fn check(&self) -> HealthCheckFuture<'_> {
+        Box::pin(async { Err(String::from("backend is down")) })
+    }
+}
+
+struct SlowCheck;
+
+impl HealthCheck for SlowCheck {
+    fn name(&self) -> &'static str {
+        "slow"
+    }
+
+    fn check(&self) -> HealthCheckFuture<'_> {
+        Box::pin(async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(())
+        })
+    }
+}
+
+fn app(health_checks: HealthChecks) -> (Router, Readiness) {
+    let readiness: Readiness = Readiness::new();
+    let app: Router = Router::new()
+        .route("/health/live", get(health_live))
+        .route("/health/ready", get(health_ready::<TestState>))
+        .with_state(Arc::new(TestState {
+            readiness: readiness.clone(),
+            health_checks,
+        }));
+    (app, readiness)
+}
+
+async fn get_path(app: &Router, path: &str) -> (StatusCode, Value) {
+    let request: Request<Body> = Request::builder().uri(path).body(Body::empty()).unwrap();
+    let response: Response = app.clone().oneshot(request).await.unwrap();
+    let status: StatusCode = response.status();
+    let body: Vec<u8> = to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap()
+        .to_vec();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn readiness_reports_serving_state_and_checks() {
+    let (app, readiness) = app(HealthChecks::new());
+
+    // starting up: alive, but not ready
+    let (status, body) = get_path(&app, "/health/live").await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!("pass", body["status"]);
+    let (status, body) = get_path(&app, "/health/ready").await;
+    assert_eq!(StatusCode::SERVICE_UNAVAILABLE, status);
+    assert_eq!("fail", body["status"]);
+    assert_eq!(false, body["serving"]);
+
+    readiness.set_ready(true);
+    let (status, body) = get_path(&app, "/health/ready").await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!("pass", body["status"]);
+    assert_eq!(true, body["serving"]);
+}
+
+#[tokio::test]
+async fn readiness_fails_if_a_check_fails() {
+    let (app, readiness) = app(HealthChecks::new()
+        .timeout(Duration::from_millis(50))
+        .check(FailingCheck)
+        .check(SlowCheck));
+    readiness.set_ready(true);
+
+    let (status, body) = get_path(&app, "/health/ready").await;
+    assert_eq!(StatusCode::SERVICE_UNAVAILABLE, status);
+    assert_eq!("fail", body["status"]);
+
+    let checks: &Vec<Value> = body["checks"].as_array().unwrap();
+    assert_eq!(2, checks.len());
+    assert_eq!("failing", checks[0]["name"]);
+    assert_eq!("fail", checks[0]["status"]);
+    assert_eq!("backend is down", checks[0]["error"]);
+    assert_eq!("slow", checks[1]["name"]);
+    assert_eq!("fail", checks[1]["status"]);
+    assert!(checks[1]["latency_ms"].as_f64().unwrap() >= 50.0);
+}
+
+#[tokio::test]
+async fn informational_checks_only_degrade_readiness() {
+    let (app, readiness) = app(HealthChecks::new()
+        .informational(FailingCheck)
+        .informational(TemplateRegistryCheck::new(
+            &TemplateRegistry::new(Vec::new()).unwrap(),
+        )));
+    readiness.set_ready(true);
+
+    let (status, body) = get_path(&app, "/health/ready").await;
+    assert_eq!(StatusCode::OK, status);
+    assert_eq!("warn", body["status"]);
+    assert_eq!("warn", body["checks"][0]["status"]);
+    assert_eq!("backend is down", body["checks"][0]["error"]);
+    assert_eq!("templates", body["checks"][1]["name"]);
+    assert_eq!("warn", body["checks"][1]["status"]);
+    assert_eq!("no templates are loaded", body["checks"][1]["error"]);
+
+    // but not serving still fails
+    readiness.set_ready(false);
+    let (status, body) = get_path(&app, "/health/ready").await;
+    assert_eq!(StatusCode::SERVICE_UNAVAILABLE, status);
+    assert_eq!("fail", body["status"]);
+}
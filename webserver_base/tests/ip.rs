@@ -0,0 +1,44 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::http::{HeaderMap, HeaderValue};
+use webserver_base::ip::{IpAnonymization, IpAnonymizer, resolve_true_client_ip_address};
+
+#[test]
+fn truncate_ipv4_to_24() {
+    let ip_anonymizer: IpAnonymizer = IpAnonymizer::new(IpAnonymization::Truncate);
+    assert_eq!("203.0.113.0", ip_anonymizer.anonymize("203.0.113.42"));
+}
+
+#[test]
+fn truncate_ipv6_to_48() {
+    let ip_anonymizer: IpAnonymizer = IpAnonymizer::new(IpAnonymization::Truncate);
+    assert_eq!(
+        "2001:db8:85a3::",
+        ip_anonymizer.anonymize("2001:db8:85a3:8d3:1319:8a2e:370:7348")
+    );
+}
+
+#[test]
+fn hash_is_stable_within_a_day() {
+    let ip_anonymizer: IpAnonymizer = IpAnonymizer::new(IpAnonymization::Hash);
+    let hashed: String = ip_anonymizer.anonymize("203.0.113.42");
+    assert!(!hashed.contains("203.0.113"));
+    assert_eq!(hashed, ip_anonymizer.anonymize("203.0.113.42"));
+    assert_ne!(hashed, ip_anonymizer.anonymize("203.0.113.43"));
+}
+
+#[test]
+fn resolve_returns_full_ip() {
+    let mut header_map: HeaderMap = HeaderMap::new();
+    header_map.insert(
+        "X-Forwarded-For",
+        HeaderValue::from_static("203.0.113.42, 10.0.0.1"),
+    );
+    let socket_addr: SocketAddr = SocketAddr::new(IpAddr::from([10, 0, 0, 1]), 8080);
+
+    let ip_anonymizer: IpAnonymizer = IpAnonymizer::new(IpAnonymization::Hash);
+    assert_eq!(
+        "203.0.113.42",
+        resolve_true_client_ip_address(socket_addr, &header_map, &ip_anonymizer)
+    );
+}
@@ -0,0 +1,102 @@
+use std::net::SocketAddr;
+
+use axum::Router;
+use axum::body::{Body, to_bytes};
+use axum::extract::ConnectInfo;
+use axum::http::{Request, StatusCode};
+use axum::response::Response;
+use axum::routing::get;
+use tower::ServiceExt;
+use webserver_base::ip::IpNetwork;
+use webserver_base::metrics::{Metrics, MetricsAccess, metrics_router};
+use webserver_base::server::UNIX_SOCKET_PEER_ADDR;
+
+async fn get_path(app: &Router, path: &str, peer: &str, forwarded: bool) -> (StatusCode, String) {
+    let mut request = Request::builder().uri(path);
+    if forwarded {
+        request = request.header("x-forwarded-for", "203.0.113.7");
+    }
+    let mut request: Request<Body> = request.body(Body::empty()).unwrap();
+    request
+        .extensions_mut()
+        .insert(ConnectInfo(peer.parse::<SocketAddr>().unwrap()));
+
+    let response: Response = app.clone().oneshot(request).await.unwrap();
+    let status: StatusCode = response.status();
+    let body: Vec<u8> = to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap()
+        .to_vec();
+    (status, String::from_utf8(body).unwrap())
+}
+
+#[tokio::test]
+async fn records_requests_by_route() {
+    let metrics: Metrics = Metrics::new();
+    let app: Router = Router::new()
+        .route("/posts/:id", get(|| async { "post" }))
+        .merge(metrics_router(metrics.clone(), MetricsAccess::Internal))
+        .layer(axum::middleware::from_fn_with_state(
+            metrics.clone(),
+            Metrics::middleware,
+        ));
+
+    for path in ["/posts/1", "/posts/2", "/wp-login.php", "/metrics"] {
+        get_path(&app, path, "127.0.0.1:1234", false).await;
+    }
+
+    let (status, body): (StatusCode, String) =
+        get_path(&app, "/metrics", "127.0.0.1:1234", false).await;
+    assert_eq!(StatusCode::OK, status);
+    assert!(
+        body.contains(r#"http_requests_total{method="GET",route="/posts/:id",status="200"} 2"#)
+    );
+    assert!(body.contains(r#"http_requests_total{method="GET",route="unmatched",status="404"} 1"#));
+    assert!(
+        body.contains(r#"http_request_duration_seconds_count{method="GET",route="/posts/:id"} 2"#)
+    );
+    assert!(!body.contains("/posts/1"));
+    // scrapes are not traffic
+    assert!(!body.contains(r#"route="/metrics""#));
+}
+
+#[tokio::test]
+async fn allowlist_refuses_other_and_proxied_clients() {
+    let app: Router = metrics_router(
+        Metrics::new(),
+        MetricsAccess::Allowlist(vec![IpNetwork::LOCALHOST_V4, "10.0.0.0/8".parse().unwrap()]),
+    );
+
+    assert_eq!(
+        StatusCode::OK,
+        get_path(&app, "/metrics", "127.0.0.1:1234", false).await.0
+    );
+    assert_eq!(
+        StatusCode::OK,
+        get_path(&app, "/metrics", "10.1.2.3:1234", false).await.0
+    );
+    // dual-stack listeners see IPv4 clients as IPv4-mapped IPv6 addresses
+    assert_eq!(
+        StatusCode::OK,
+        get_path(&app, "/metrics", "[::ffff:127.0.0.1]:1234", false)
+            .await
+            .0
+    );
+    assert_eq!(
+        StatusCode::FORBIDDEN,
+        get_path(&app, "/metrics", "192.0.2.1:1234", false).await.0
+    );
+    // e.g. a public request through a reverse proxy on localhost
+    assert_eq!(
+        StatusCode::FORBIDDEN,
+        get_path(&app, "/metrics", "127.0.0.1:1234", true).await.0
+    );
+    // Unix socket peers, whose address is a placeholder
+    assert_eq!(
+        StatusCode::FORBIDDEN,
+        get_path(&app, "/metrics", &UNIX_SOCKET_PEER_ADDR.to_string(), false)
+            .await
+            .0
+    );
+    assert!("10.0.0.0/33".parse::<IpNetwork>().is_err());
+}
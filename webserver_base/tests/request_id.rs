@@ -0,0 +1,56 @@
+use axum::body::{Body, to_bytes};
+use axum::http::{Request, StatusCode};
+use axum::response::Response;
+use axum::routing::get;
+use axum::{Extension, Router};
+use tower::ServiceExt;
+use webserver_base::request_id::{REQUEST_ID_HEADER, RequestId, request_id_middleware};
+
+fn app() -> Router {
+    Router::new()
+        .route(
+            "/",
+            get(
+                |Extension(request_id): Extension<RequestId>| async move { request_id.to_string() },
+            ),
+        )
+        .layer(axum::middleware::from_fn(request_id_middleware))
+}
+
+async fn get_with_request_id(request_id: Option<&str>) -> (String, String) {
+    let mut request = Request::builder().uri("/");
+    if let Some(request_id) = request_id {
+        request = request.header(REQUEST_ID_HEADER, request_id);
+    }
+    let response: Response = app()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(StatusCode::OK, response.status());
+
+    let echoed: String = response.headers()[REQUEST_ID_HEADER]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let body: String =
+        String::from_utf8(to_bytes(response.into_body(), 1024).await.unwrap().to_vec()).unwrap();
+    (echoed, body)
+}
+
+#[tokio::test]
+async fn reads_or_generates_request_id() {
+    // incoming IDs (e.g. from a reverse proxy) are kept
+    let (echoed, handled): (String, String) = get_with_request_id(Some("proxy-1234")).await;
+    assert_eq!("proxy-1234", echoed);
+    assert_eq!("proxy-1234", handled);
+
+    // otherwise one is generated
+    let (echoed, handled): (String, String) = get_with_request_id(None).await;
+    assert_eq!(32, echoed.len());
+    assert_eq!(echoed, handled);
+
+    // invalid incoming IDs are replaced
+    let (echoed, handled): (String, String) = get_with_request_id(Some(&"x".repeat(200))).await;
+    assert_eq!(32, echoed.len());
+    assert_eq!(echoed, handled);
+}
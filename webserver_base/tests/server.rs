@@ -0,0 +1,399 @@
+use std::fs;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use axum::Router;
+use axum::extract::ConnectInfo;
+use axum::routing::get;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+#[cfg(unix)]
+use tokio::net::UnixStream;
+use tokio::sync::oneshot;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use webserver_base::server::{
+    Listener, ListenerConfig, Readiness, ServerRunner, TlsConfig, UNIX_SOCKET_PEER_ADDR,
+    serve_listeners,
+};
+
+#[test]
+fn parse_listener_configs() {
+    assert_eq!(
+        ListenerConfig::Tcp("[::]:8080".parse().unwrap()),
+        "tcp://[::]:8080".parse().unwrap()
+    );
+    #[cfg(unix)]
+    assert_eq!(
+        ListenerConfig::Unix {
+            path: PathBuf::from("/run/site/site.sock"),
+            mode: Some(0o660),
+        },
+        "unix:/run/site/site.sock?mode=660".parse().unwrap()
+    );
+    assert_eq!(
+        ListenerConfig::HttpsRedirect {
+            addr: "[::]:8080".parse().unwrap(),
+            https_port: 8443,
+        },
+        "redirect://[::]:8080?https_port=8443".parse().unwrap()
+    );
+    assert!("localhost:8080".parse::<ListenerConfig>().is_err());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn serve_unix_socket() {
+    let path: PathBuf =
+        std::env::temp_dir().join(format!("webserver-base-{}.sock", std::process::id()));
+    let listener: Listener = Listener::bind(
+        &ListenerConfig::Unix {
+            path: path.clone(),
+            mode: Some(0o600),
+        },
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        0o600,
+        fs::metadata(&path).unwrap().permissions().mode() & 0o777
+    );
+
+    let app: Router = Router::new().route(
+        "/",
+        get(|ConnectInfo(addr): ConnectInfo<SocketAddr>| async move { addr.to_string() }),
+    );
+    let (shutdown_sender, shutdown_receiver) = oneshot::channel::<()>();
+    let server = tokio::spawn(serve_listeners(vec![listener], app, async move {
+        let _ = shutdown_receiver.await;
+    }));
+
+    let mut stream: UnixStream = UnixStream::connect(&path).await.unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response: String = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.ends_with(&UNIX_SOCKET_PEER_ADDR.to_string()));
+
+    shutdown_sender.send(()).unwrap();
+    server.await.unwrap().unwrap();
+    assert!(!path.exists());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn unix_listener_does_not_replace_other_files() {
+    let path: PathBuf =
+        std::env::temp_dir().join(format!("webserver-base-{}.txt", std::process::id()));
+    fs::write(&path, "not a socket").unwrap();
+
+    assert!(
+        Listener::bind(
+            &ListenerConfig::Unix {
+                path: path.clone(),
+                mode: None,
+            },
+            None,
+        )
+        .is_err()
+    );
+    assert_eq!("not a socket", fs::read_to_string(&path).unwrap());
+    fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn serve_https_and_redirect() {
+    let dir: PathBuf =
+        std::env::temp_dir().join(format!("webserver-base-tls-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let certified_key: rcgen::CertifiedKey<rcgen::KeyPair> =
+        rcgen::generate_simple_self_signed(vec![String::from("localhost")]).unwrap();
+    let tls_config: TlsConfig = TlsConfig::new(dir.join("cert.pem"), dir.join("key.pem"));
+    fs::write(&tls_config.cert_path, certified_key.cert.pem()).unwrap();
+    fs::write(
+        &tls_config.key_path,
+        certified_key.signing_key.serialize_pem(),
+    )
+    .unwrap();
+
+    let https_listener: Listener = Listener::bind(
+        &ListenerConfig::Https("127.0.0.1:0".parse().unwrap()),
+        Some(&tls_config),
+    )
+    .unwrap();
+    let Listener::Https(tcp_listener, _) = &https_listener else {
+        panic!("expected an HTTPS listener");
+    };
+    let https_addr: SocketAddr = tcp_listener.local_addr().unwrap();
+    let redirect_listener: Listener = Listener::bind(
+        &ListenerConfig::HttpsRedirect {
+            addr: "127.0.0.1:0".parse().unwrap(),
+            https_port: https_addr.port(),
+        },
+        None,
+    )
+    .unwrap();
+    let Listener::HttpsRedirect(tcp_listener, _) = &redirect_listener else {
+        panic!("expected an HTTPS redirect listener");
+    };
+    let redirect_addr: SocketAddr = tcp_listener.local_addr().unwrap();
+
+    let app: Router = Router::new().route("/", get(|| async { "secure" }));
+    let (shutdown_sender, shutdown_receiver) = oneshot::channel::<()>();
+    let server = tokio::spawn(serve_listeners(
+        vec![https_listener, redirect_listener],
+        app,
+        async move {
+            let _ = shutdown_receiver.await;
+        },
+    ));
+
+    // HTTPS, trusting only the self-signed certificate
+    let mut root_cert_store: RootCertStore = RootCertStore::empty();
+    root_cert_store
+        .add(certified_key.cert.der().clone())
+        .unwrap();
+    let client_config: ClientConfig = ClientConfig::builder_with_provider(Arc::new(
+        tokio_rustls::rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .unwrap()
+    .with_root_certificates(root_cert_store)
+    .with_no_client_auth();
+    let mut stream = TlsConnector::from(Arc::new(client_config))
+        .connect(
+            "localhost".try_into().unwrap(),
+            TcpStream::connect(https_addr).await.unwrap(),
+        )
+        .await
+        .unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response: Vec<u8> = Vec::new();
+    // the server may close the connection without a TLS close_notify
+    let _ = stream.read_to_end(&mut response).await;
+    let response: String = String::from_utf8(response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.ends_with("secure"));
+
+    // HTTP -> HTTPS
+    let mut stream: TcpStream = TcpStream::connect(redirect_addr).await.unwrap();
+    stream
+        .write_all(b"GET /about?x=1 HTTP/1.1\r\nHost: localhost:80\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response: String = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 308 Permanent Redirect"));
+    assert!(response.contains(&format!(
+        "location: https://localhost:{}/about?x=1",
+        https_addr.port()
+    )));
+
+    shutdown_sender.send(()).unwrap();
+    server.await.unwrap().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Whether a TLS handshake with `addr` succeeds when trusting only `trusted`.
+async fn handshake_trusting(addr: SocketAddr, trusted: &rcgen::Certificate) -> bool {
+    let mut root_cert_store: RootCertStore = RootCertStore::empty();
+    root_cert_store.add(trusted.der().clone()).unwrap();
+    let client_config: ClientConfig = ClientConfig::builder_with_provider(Arc::new(
+        tokio_rustls::rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .unwrap()
+    .with_root_certificates(root_cert_store)
+    .with_no_client_auth();
+    TlsConnector::from(Arc::new(client_config))
+        .connect(
+            "localhost".try_into().unwrap(),
+            TcpStream::connect(addr).await.unwrap(),
+        )
+        .await
+        .is_ok()
+}
+
+#[tokio::test]
+async fn https_reloads_renewed_certificates() {
+    let dir: PathBuf =
+        std::env::temp_dir().join(format!("webserver-base-tls-reload-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let mut tls_config: TlsConfig = TlsConfig::new(dir.join("cert.pem"), dir.join("key.pem"));
+    tls_config.reload_interval = Duration::from_millis(50);
+    let old: rcgen::CertifiedKey<rcgen::KeyPair> =
+        rcgen::generate_simple_self_signed(vec![String::from("localhost")]).unwrap();
+    fs::write(&tls_config.cert_path, old.cert.pem()).unwrap();
+    fs::write(&tls_config.key_path, old.signing_key.serialize_pem()).unwrap();
+
+    let listener: Listener = Listener::bind(
+        &ListenerConfig::Https("127.0.0.1:0".parse().unwrap()),
+        Some(&tls_config),
+    )
+    .unwrap();
+    let Listener::Https(tcp_listener, _) = &listener else {
+        panic!("expected an HTTPS listener");
+    };
+    let addr: SocketAddr = tcp_listener.local_addr().unwrap();
+    let app: Router = Router::new().route("/", get(|| async { "secure" }));
+    let (shutdown_sender, shutdown_receiver) = oneshot::channel::<()>();
+    let server = tokio::spawn(serve_listeners(vec![listener], app, async move {
+        let _ = shutdown_receiver.await;
+    }));
+    assert!(handshake_trusting(addr, &old.cert).await);
+
+    // halfway through a renewal, the new certificate does not match the old key yet
+    let new: rcgen::CertifiedKey<rcgen::KeyPair> =
+        rcgen::generate_simple_self_signed(vec![String::from("localhost")]).unwrap();
+    fs::write(&tls_config.cert_path, new.cert.pem()).unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(handshake_trusting(addr, &old.cert).await);
+
+    fs::write(&tls_config.key_path, new.signing_key.serialize_pem()).unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(handshake_trusting(addr, &new.cert).await);
+    assert!(!handshake_trusting(addr, &old.cert).await);
+
+    shutdown_sender.send(()).unwrap();
+    server.await.unwrap().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn graceful_shutdown_drains_requests_and_runs_hooks() {
+    let listener: Listener =
+        Listener::bind(&ListenerConfig::Tcp("127.0.0.1:0".parse().unwrap()), None).unwrap();
+    let Listener::Tcp(tcp_listener) = &listener else {
+        panic!("expected a TCP listener");
+    };
+    let addr: SocketAddr = tcp_listener.local_addr().unwrap();
+
+    let app: Router = Router::new().route(
+        "/slow",
+        get(|| async {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            "done"
+        }),
+    );
+    let readiness: Readiness = Readiness::new();
+    let hook_ran: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+    let (shutdown_sender, shutdown_receiver) = oneshot::channel::<()>();
+    let server = tokio::spawn(
+        ServerRunner::new(vec![listener], app)
+            .readiness(readiness.clone())
+            .shutdown_signal(async move {
+                let _ = shutdown_receiver.await;
+            })
+            .on_shutdown("test", {
+                let hook_ran: Arc<AtomicBool> = Arc::clone(&hook_ran);
+                move || async move { hook_ran.store(true, Ordering::SeqCst) }
+            })
+            .run(),
+    );
+
+    let mut stream: TcpStream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"GET /slow HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(readiness.is_ready());
+
+    // the in-flight request still completes after shutting down
+    shutdown_sender.send(()).unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!readiness.is_ready());
+    let mut response: String = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.ends_with("done"));
+
+    server.await.unwrap().unwrap();
+    assert!(hook_ran.load(Ordering::SeqCst));
+    assert!(TcpStream::connect(addr).await.is_err());
+}
+
+/// Records when the request handler is dropped (i.e. cancelled, since it never finishes).
+#[cfg(unix)]
+struct DropRecorder(Arc<Mutex<Vec<&'static str>>>);
+
+#[cfg(unix)]
+impl Drop for DropRecorder {
+    fn drop(&mut self) {
+        self.0.lock().unwrap().push("handler cancelled");
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn drain_timeout_closes_connections_before_running_hooks() {
+    let path: PathBuf =
+        std::env::temp_dir().join(format!("webserver-base-drain-{}.sock", std::process::id()));
+    let listener: Listener = Listener::bind(
+        &ListenerConfig::Unix {
+            path: path.clone(),
+            mode: None,
+        },
+        None,
+    )
+    .unwrap();
+
+    let events: Arc<Mutex<Vec<&'static str>>> = Arc::new(Mutex::new(Vec::new()));
+    let app: Router = Router::new().route(
+        "/stuck",
+        get({
+            let events: Arc<Mutex<Vec<&'static str>>> = Arc::clone(&events);
+            move || async move {
+                let _recorder: DropRecorder = DropRecorder(events);
+                tokio::time::sleep(Duration::from_mins(1)).await;
+                "done"
+            }
+        }),
+    );
+    let (shutdown_sender, shutdown_receiver) = oneshot::channel::<()>();
+    let server = tokio::spawn(
+        ServerRunner::new(vec![listener], app)
+            .drain_timeout(Duration::from_millis(100))
+            .shutdown_signal(async move {
+                let _ = shutdown_receiver.await;
+            })
+            .on_shutdown("test", {
+                let events: Arc<Mutex<Vec<&'static str>>> = Arc::clone(&events);
+                move || async move { events.lock().unwrap().push("hook") }
+            })
+            .run(),
+    );
+
+    let mut stream: UnixStream = UnixStream::connect(&path).await.unwrap();
+    stream
+        .write_all(b"GET /stuck HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    shutdown_sender.send(()).unwrap();
+
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(vec!["handler cancelled", "hook"], *events.lock().unwrap());
+    assert!(!path.exists());
+
+    // the connection was closed without a response
+    let mut response: String = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.is_empty());
+}
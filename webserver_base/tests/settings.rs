@@ -0,0 +1,262 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use webserver_base::base_settings::{
+    BaseSettings, Environment, LogFormat, Secret, SettingsError, SettingsLoader,
+};
+
+// an env var prefix that nothing sets, so the tests only see the file and argument layers
+const ENV_PREFIX: &str = "WEBSERVER_BASE_SETTINGS_TEST_";
+
+fn write_config_file(name: &str, contents: &str) -> PathBuf {
+    let path: PathBuf = std::env::temp_dir().join(format!("{}-{name}", std::process::id()));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn layers_and_extra() {
+    let path: PathBuf = write_config_file(
+        "config.toml",
+        r#"
+environment = "production"
+port = 3000
+project_name = "Example"
+project_description = "An example site"
+project_keywords = "example"
+home_url = "https://example.com"
+analytics_domain = "example.com"
+sentry_dsn = ""
+
+[extra]
+newsletter_url = "https://example.com/newsletter"
+"#,
+    );
+
+    let (settings, extra): (BaseSettings, BTreeMap<String, String>) = SettingsLoader::new()
+        .file(&path)
+        .env_prefix(ENV_PREFIX)
+        .args(
+            [
+                "--port=4000",
+                "--project-name",
+                "Overridden",
+                "--log-format=pretty",
+            ]
+            .map(String::from),
+        )
+        .load_with_extra()
+        .unwrap();
+
+    assert!(settings.environment == Environment::Production);
+    assert_eq!("0.0.0.0", settings.host);
+    assert_eq!(4000, settings.port);
+    assert_eq!("Overridden", settings.project_name);
+    assert_eq!(LogFormat::Pretty, settings.log_format);
+    assert!(settings.sentry_dsn.is_none());
+    // `/metrics` is not served on the main listeners unless explicitly allowlisted
+    assert!(settings.metrics_allowlist.is_empty());
+    assert_eq!(Some("example.com"), settings.reported_analytics_domain());
+    assert_eq!(
+        Some("https://example.com/newsletter"),
+        extra.get("newsletter_url").map(String::as_str)
+    );
+
+    fs::remove_file(path).unwrap();
+}
+
+#[derive(Debug, Deserialize)]
+struct Extra {
+    zip: String,
+    nickname: Option<String>,
+    page_size: u32,
+    tags: Vec<String>,
+}
+
+#[test]
+fn extra_values_from_env_and_args_keep_their_text() {
+    let path: PathBuf = write_config_file(
+        "extra.toml",
+        r#"
+environment = "production"
+project_name = "Example"
+project_description = "An example site"
+project_keywords = "example"
+home_url = "https://example.com"
+analytics_domain = "example.com"
+
+[extra]
+tags = ["rust"]
+"#,
+    );
+    let env_vars = |prefix: &str| {
+        [
+            (format!("{prefix}EXTRA_ZIP"), String::from("01234")),
+            (format!("{prefix}EXTRA_PAGE_SIZE"), String::from("20")),
+        ]
+    };
+
+    let (_, extra): (BaseSettings, Extra) = SettingsLoader::new()
+        .file(&path)
+        .env_prefix(ENV_PREFIX)
+        .env_vars(env_vars(ENV_PREFIX))
+        .args([String::from("--extra.nickname=null")])
+        .load_with_extra()
+        .unwrap();
+    assert_eq!("01234", extra.zip);
+    assert_eq!(Some("null"), extra.nickname.as_deref());
+    assert_eq!(20, extra.page_size);
+    assert_eq!(vec![String::from("rust")], extra.tags);
+
+    // unrelated `EXTRA_*` variables are not read without a prefix
+    let Err(SettingsError::Multiple(errors)) = SettingsLoader::new()
+        .file(&path)
+        .env_vars(env_vars(""))
+        .load_with_extra::<Extra>()
+    else {
+        panic!("expected the extra settings to be missing");
+    };
+    assert!(
+        errors
+            .iter()
+            .any(|e| matches!(e, SettingsError::InvalidExtra(reason) if reason.contains("zip")))
+    );
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn secrets_from_files() {
+    let path: PathBuf = write_config_file(
+        "secrets.toml",
+        r#"
+project_name = "Example"
+project_description = "An example site"
+project_keywords = "example"
+home_url = "https://example.com"
+"#,
+    );
+    let secret_path: PathBuf =
+        write_config_file("sentry_dsn", "https://key@sentry.example.com/1\n");
+
+    let settings: BaseSettings = SettingsLoader::new()
+        .file(&path)
+        .env_prefix(ENV_PREFIX)
+        .env_vars([(
+            format!("{ENV_PREFIX}SENTRY_DSN_FILE"),
+            secret_path.to_string_lossy().to_string(),
+        )])
+        .load()
+        .unwrap();
+    assert_eq!(
+        Some("https://key@sentry.example.com/1"),
+        settings
+            .sentry_dsn
+            .as_ref()
+            .map(|secret| secret.expose().as_str())
+    );
+    let debug: String = format!("{settings:?}");
+    assert!(debug.contains("sentry_dsn: Some(Secret([REDACTED]))"));
+    assert!(!debug.contains("key@"));
+
+    let missing_path: PathBuf = secret_path.with_extension("missing");
+    let Err(SettingsError::Multiple(errors)) = SettingsLoader::new()
+        .file(&path)
+        .env_prefix(ENV_PREFIX)
+        .env_vars([(
+            format!("{ENV_PREFIX}SENTRY_DSN_FILE"),
+            missing_path.to_string_lossy().to_string(),
+        )])
+        .load()
+    else {
+        panic!("expected the missing secret file to be reported");
+    };
+    assert!(
+        errors
+            .iter()
+            .any(|e| matches!(e, SettingsError::FileIOError(path, _) if *path == missing_path))
+    );
+
+    fs::remove_file(path).unwrap();
+    fs::remove_file(secret_path).unwrap();
+}
+
+#[test]
+fn reports_every_error() {
+    let path: PathBuf = write_config_file("config.yaml", "port: not-a-port\nunknown_key: 1\n");
+
+    let Err(SettingsError::Multiple(errors)) = SettingsLoader::new()
+        .file(&path)
+        .env_prefix(ENV_PREFIX)
+        .load()
+    else {
+        panic!("expected missing and invalid settings");
+    };
+
+    // unknown key, invalid port, and 4 missing settings
+    assert_eq!(6, errors.len());
+    assert!(
+        errors
+            .iter()
+            .any(|e| matches!(e, SettingsError::Unknown(key) if key == "unknown_key"))
+    );
+    assert!(
+        errors
+            .iter()
+            .any(|e| matches!(e, SettingsError::Invalid { key, .. } if key == "port"))
+    );
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn empty_analytics_domain_disables_analytics() {
+    let path: PathBuf = write_config_file(
+        "empty_analytics_domain.toml",
+        r#"
+environment = "production"
+project_name = "Example"
+project_description = "An example site"
+project_keywords = "example"
+home_url = "https://example.com"
+analytics_domain = "example.com"
+"#,
+    );
+
+    // e.g. `ANALYTICS_DOMAIN=` from a deployment template
+    let settings: BaseSettings = SettingsLoader::new()
+        .file(&path)
+        .env_prefix(ENV_PREFIX)
+        .env_vars([(format!("{ENV_PREFIX}ANALYTICS_DOMAIN"), String::new())])
+        .load()
+        .unwrap();
+    assert!(settings.analytics_domain.is_none());
+    assert!(settings.reported_analytics_domain().is_none());
+
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn secrets_are_redacted() {
+    let secret: Secret = Secret::new(String::from("https://key@sentry.example.com/1"));
+
+    assert_eq!("[REDACTED]", secret.to_string());
+    assert!(!format!("{secret:?}").contains("key"));
+    assert_eq!("https://key@sentry.example.com/1", secret.expose());
+}
+
+#[test]
+fn environment_profiles() {
+    let staging: Environment = "Staging".parse().unwrap();
+    assert_eq!(Environment::Staging, staging);
+    assert_eq!("0.0.0.0", staging.profile().bind_host);
+    assert!(!Environment::Test.profile().analytics_enabled);
+    assert!(!Environment::Production.profile().verbose_errors);
+    assert_eq!(
+        LogFormat::Json,
+        Environment::Production.profile().log_format
+    );
+    assert!("qa".parse::<Environment>().is_err());
+}
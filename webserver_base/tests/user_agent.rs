@@ -0,0 +1,76 @@
+use webserver_base::user_agent::{UserAgentClass, UserAgentClassifier};
+
+#[test]
+fn classify_builtin_rules() {
+    let classifier: UserAgentClassifier = UserAgentClassifier::default();
+
+    assert_eq!(
+        UserAgentClass::Browser,
+        classifier.classify(
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/128.0.0.0 Safari/537.36"
+        )
+    );
+    assert_eq!(
+        UserAgentClass::Crawler,
+        classifier
+            .classify("Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)")
+    );
+    assert_eq!(
+        UserAgentClass::HeadlessBrowser,
+        classifier.classify(
+            "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) HeadlessChrome/128.0.0.0 Safari/537.36"
+        )
+    );
+    assert_eq!(
+        UserAgentClass::UptimeMonitor,
+        classifier
+            .classify("Mozilla/5.0+(compatible; UptimeRobot/2.0; http://www.uptimerobot.com/)")
+    );
+    assert_eq!(
+        UserAgentClass::Crawler,
+        classifier
+            .classify("Mozilla/5.0 (compatible; bingbot/2.0; +http://www.bing.com/bingbot.htm)")
+    );
+    for crawler in [
+        "Slackbot-LinkExpanding 1.0 (+https://api.slack.com/robots)",
+        "DuckDuckBot-Https/1.1; (+https://duckduckgo.com/duckduckbot)",
+        "Mozilla/5.0 (compatible; Discordbot/2.0; +https://discordapp.com)",
+        "Mozilla/5.0 (compatible; Baiduspider/2.0; +http://www.baidu.com/search/spider.html)",
+    ] {
+        assert_eq!(UserAgentClass::Crawler, classifier.classify(crawler));
+    }
+    // Electron desktop apps and phones with "bot" in their model name are browsers
+    assert_eq!(
+        UserAgentClass::Browser,
+        classifier.classify(
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Slack/4.41.97 Chrome/128.0.0.0 Electron/32.2.0 Safari/537.36"
+        )
+    );
+    assert_eq!(
+        UserAgentClass::Browser,
+        classifier.classify(
+            "Mozilla/5.0 (Linux; Android 13; CUBOT KINGKONG 9) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/128.0.0.0 Mobile Safari/537.36"
+        )
+    );
+    assert_eq!(UserAgentClass::Empty, classifier.classify("  "));
+}
+
+#[test]
+fn classify_custom_rules() {
+    let classifier: UserAgentClassifier =
+        UserAgentClassifier::new().rule("InternalHealthProbe", UserAgentClass::UptimeMonitor);
+
+    assert_eq!(
+        UserAgentClass::UptimeMonitor,
+        classifier.classify("internalhealthprobe/1.0")
+    );
+    assert_eq!(UserAgentClass::Browser, classifier.classify("curl/8.0.1"));
+
+    let classifier: UserAgentClassifier =
+        UserAgentClassifier::new().word_ending_rule("Probe", UserAgentClass::UptimeMonitor);
+    assert_eq!(
+        UserAgentClass::UptimeMonitor,
+        classifier.classify("InternalHealthProbe-Https/1.0")
+    );
+    assert_eq!(UserAgentClass::Browser, classifier.classify("probes/1.0"));
+}
@@ -0,0 +1,83 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use axum::Router;
+use axum::routing::{get, post};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+use webserver_base::{
+    base_settings::BaseSettings,
+    web_server::{WebApp, WebServer},
+};
+
+fn settings(socket_path: &Path) -> BaseSettings {
+    common::settings(&[
+        ("ENVIRONMENT", "test"),
+        ("LISTEN", &format!("unix:{}", socket_path.display())),
+    ])
+}
+
+async fn request(socket_path: &Path, request: &str) -> String {
+    let mut stream: UnixStream = UnixStream::connect(socket_path).await.unwrap();
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response: String = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+#[test]
+fn serves_app_with_standard_middleware_until_shutdown() {
+    let socket_path: PathBuf = std::env::temp_dir().join(format!(
+        "webserver-base-web-server-{}.sock",
+        std::process::id()
+    ));
+    let responses: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let shutdown_hook_ran: Arc<Mutex<bool>> = Arc::new(Mutex::new(false));
+
+    let result: io::Result<()> = WebServer::new(settings(&socket_path))
+        .body_limit(16)
+        .server_runner({
+            // make the requests, then shut down
+            let socket_path: PathBuf = socket_path.clone();
+            let responses: Arc<Mutex<Vec<String>>> = Arc::clone(&responses);
+            move |server_runner| {
+                server_runner.shutdown_signal(async move {
+                    let hello: String = request(
+                        &socket_path,
+                        "GET /hello HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+                    )
+                    .await;
+                    let too_large: String = request(
+                        &socket_path,
+                        "POST /echo HTTP/1.1\r\nHost: localhost\r\nContent-Length: 32\r\nConnection: close\r\n\r\n0123456789abcdef0123456789abcdef",
+                    )
+                    .await;
+                    responses.lock().unwrap().extend([hello, too_large]);
+                })
+            }
+        })
+        .run({
+            let shutdown_hook_ran: Arc<Mutex<bool>> = Arc::clone(&shutdown_hook_ran);
+            |_settings: BaseSettings| async move {
+                let router: Router = Router::new()
+                    .route("/hello", get(|| async { "hello" }))
+                    .route("/echo", post(|body: String| async move { body }));
+                Ok(WebApp::new(router).on_shutdown("test", move || async move {
+                    *shutdown_hook_ran.lock().unwrap() = true;
+                }))
+            }
+        });
+
+    result.unwrap();
+    let responses: Vec<String> = responses.lock().unwrap().clone();
+    assert!(responses[0].starts_with("HTTP/1.1 200 OK"));
+    assert!(responses[0].ends_with("hello"));
+    assert!(responses[1].starts_with("HTTP/1.1 413 Payload Too Large"));
+    assert!(*shutdown_hook_ran.lock().unwrap());
+    assert!(!socket_path.exists());
+}
@@ -0,0 +1,80 @@
+//! Build script helpers for `webserver-base` projects; only depends on `chrono`, so build scripts stay cheap to
+//! compile.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+/// Captures the build information that `webserver_base::build_info!()` reads; call it from the crate's build script:
+///
+/// ```ignore
+/// fn main() {
+///     webserver_base_build::emit();
+/// }
+/// ```
+///
+/// The git commit is `GIT_COMMIT` if set (e.g. by a Docker build, which has no `.git` directory), and the build
+/// timestamp is `SOURCE_DATE_EPOCH` if set (for reproducible builds).
+pub fn emit() {
+    println!("cargo:rerun-if-env-changed=GIT_COMMIT");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    for git_file in git_files() {
+        println!("cargo:rerun-if-changed={}", git_file.display());
+    }
+
+    if let Some(git_commit) = git_commit() {
+        println!("cargo:rustc-env=BUILD_GIT_COMMIT={git_commit}");
+    }
+    println!("cargo:rustc-env=BUILD_TIMESTAMP={}", build_timestamp());
+    if let Some(rustc_version) = rustc_version() {
+        println!("cargo:rustc-env=BUILD_RUSTC_VERSION={rustc_version}");
+    }
+}
+
+fn git_commit() -> Option<String> {
+    if let Ok(git_commit) = env::var("GIT_COMMIT") {
+        return Some(git_commit);
+    }
+
+    run("git", &["rev-parse", "--short=12", "HEAD"])
+}
+
+/// The files that change when a commit is checked out or made, so that the commit is captured again (not the index,
+/// which changes on every `git add`).
+fn git_files() -> Vec<PathBuf> {
+    let Some(git_dir) = run("git", &["rev-parse", "--git-dir"]).map(PathBuf::from) else {
+        return Vec::new();
+    };
+
+    let mut git_files: Vec<PathBuf> = vec![git_dir.join("HEAD")];
+    if let Some(head_ref) = run("git", &["symbolic-ref", "-q", "HEAD"]) {
+        git_files.push(git_dir.join(head_ref));
+    }
+    git_files.retain(|git_file| git_file.exists());
+    git_files
+}
+
+fn build_timestamp() -> String {
+    let timestamp: DateTime<Utc> = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<i64>().ok())
+        .and_then(|epoch| DateTime::from_timestamp(epoch, 0))
+        .unwrap_or_else(Utc::now);
+    timestamp.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn rustc_version() -> Option<String> {
+    let rustc: String = env::var("RUSTC").unwrap_or_else(|_| String::from("rustc"));
+    run(&rustc, &["--version"])
+}
+
+/// The trimmed output of a command, or `None` if it could not be run or failed.
+fn run(program: &str, args: &[&str]) -> Option<String> {
+    let output: Output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}